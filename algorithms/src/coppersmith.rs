@@ -1,23 +1,382 @@
 // Short Message Attack
 // Partial Key Exposure Attack
 // Low Exponent Attack
+// Small Private Exponent Attack
 
-use lll_rs::lll::biglll::lattice_reduce;
-use lll_rs::matrix::Matrix;
-use lll_rs::vector::BigVector;
-use rug::ops::Pow;
-use rug::{Assign, Integer};
+use rug::ops::{Pow, RemRounding};
+use rug::Integer;
 
-// LOW EXPONENT ATTACK
+// Polynomials over the integers, lowest degree coefficient first
+type Polynomial = Vec<Integer>;
+
+// Roots found by small_roots, and the dimension of the lattice that was reduced
+pub struct SmallRoots {
+    pub roots: Vec<Integer>,
+    pub dimension: usize,
+}
+
+fn poly_mul(a: &[Integer], b: &[Integer]) -> Polynomial {
+    let mut product: Polynomial = vec![Integer::new(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+fn poly_pow(f: &[Integer], exponent: usize) -> Polynomial {
+    let mut result: Polynomial = vec![Integer::from(1)];
+    for _ in 0..exponent {
+        result = poly_mul(&result, f);
+    }
+    result
+}
+
+// Horner evaluation over the integers
+fn evaluate(f: &[Integer], x: &Integer) -> Integer {
+    let mut result = Integer::new();
+    for coefficient in f.iter().rev() {
+        result *= x;
+        result += coefficient;
+    }
+    result
+}
+
+fn derivative(f: &[Integer]) -> Polynomial {
+    f.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, coefficient)| Integer::from(coefficient * (i as u32)))
+        .collect()
+}
+
+// Drop leading zero coefficients
+fn trim(mut f: Polynomial) -> Polynomial {
+    while f.len() > 1 && f[f.len() - 1] == 0 {
+        f.pop();
+    }
+    f
+}
+
+// (x + a)^e - c, the polynomial of an RSA ciphertext whose message is known up to x
+fn shifted_power(a: &Integer, e: usize, c: &Integer) -> Polynomial {
+    let mut f = poly_pow(&[a.clone(), Integer::from(1)], e);
+    f[0] -= c;
+    f
+}
+
+// LLL REDUCTION
 
 /*
-If the exponent is small, then the modulo N operation does not wrap around the result in c = m^e mod N
-(P1) So, we can safely say c = m^e.
+Let b_0, ..., b_(n-1) be the rows, b*_i their Gram-Schmidt vectors and
+mu_ij = <b_i, b*_j> / <b*_j, b*_j>. The basis is LLL reduced when it is
+(P1) size reduced:  |mu_ij| <= 1/2                                   for j < i
+(P2) Lovasz:        |b*_i|^2 >= (delta - mu_i(i-1)^2) |b*_(i-1)|^2   for 0 < i
+
+Textbook LLL walks k up the basis: subtract round(mu_kj) b_j from b_k for j = k - 1, ..., 0 to get
+(P1), then move on if (P2) holds for k and swap b_k with b_(k-1) and step back otherwise.
+
+lll-rs 0.2.0 is not used: its biglll::lattice_reduce size reduces b_i only against b_1 ... b_(i-1)
+(the loop is `for k in 1..i`), never against b_0, so its output can fail (P1).
+
+The rows are updated exactly. The Gram-Schmidt data only decides which updates to make, so it is
+kept in floating point (Schnorr-Euchner). Coppersmith lattices have entries of thousands of bits,
+so a float carries its own exponent, and a dot product that cancels is recomputed exactly.
+*/
+
+const LOVASZ_DELTA: f64 = 0.99;
+const SIZE_REDUCTION_ETA: f64 = 0.501;
+
+// mantissa * 2^exponent with 0.5 <= |mantissa| < 1, or zero
+#[derive(Clone, Copy)]
+struct Approx {
+    mantissa: f64,
+    exponent: i64,
+}
+
+impl Approx {
+    const ZERO: Approx = Approx {
+        mantissa: 0.0,
+        exponent: 0,
+    };
+
+    // Split the exponent off the bits of x, frexp in C
+    fn new(x: f64, exponent: i64) -> Approx {
+        let bits = x.to_bits();
+        let biased = ((bits >> 52) & 0x7ff) as i64;
+        if biased == 0 {
+            if x == 0.0 {
+                return Approx::ZERO;
+            }
+            // Subnormal, scale it into the normal range first
+            return Approx::new(x * 2f64.powi(64), exponent - 64);
+        }
+        Approx {
+            mantissa: f64::from_bits((bits & !(0x7ff << 52)) | (1022 << 52)),
+            exponent: exponent + biased - 1022,
+        }
+    }
+
+    fn from_integer(x: &Integer) -> Approx {
+        let (mantissa, exponent) = x.to_f64_exp();
+        Approx::new(mantissa, exponent as i64)
+    }
+
+    fn mul(self, other: Approx) -> Approx {
+        Approx::new(
+            self.mantissa * other.mantissa,
+            self.exponent + other.exponent,
+        )
+    }
+
+    fn div(self, other: Approx) -> Approx {
+        Approx::new(
+            self.mantissa / other.mantissa,
+            self.exponent - other.exponent,
+        )
+    }
+
+    fn add(self, other: Approx) -> Approx {
+        if self.mantissa == 0.0 {
+            return other;
+        }
+        if other.mantissa == 0.0 {
+            return self;
+        }
+        let (large, small) = if self.exponent >= other.exponent {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let gap = large.exponent - small.exponent;
+        if gap > 64 {
+            return large;
+        }
+        let scale = f64::from_bits(((1023 - gap) as u64) << 52);
+        Approx::new(large.mantissa + small.mantissa * scale, large.exponent)
+    }
+
+    fn sub(self, other: Approx) -> Approx {
+        self.add(Approx {
+            mantissa: -other.mantissa,
+            exponent: other.exponent,
+        })
+    }
+
+    fn exceeds(self, bound: f64) -> bool {
+        self.mantissa != 0.0
+            && (self.exponent > 2 || self.mantissa.abs() * 2f64.powi(self.exponent as i32) > bound)
+    }
+
+    // Nearest integer, exact in the 53 bits of the mantissa
+    fn round(self) -> Integer {
+        if self.mantissa == 0.0 || self.exponent < 0 {
+            return Integer::new();
+        }
+        if self.exponent <= 53 {
+            let value = (self.mantissa * 2f64.powi(self.exponent as i32)).round();
+            return Integer::from_f64(value).unwrap();
+        }
+        Integer::from_f64(self.mantissa * 2f64.powi(53)).unwrap() << (self.exponent - 53) as u32
+    }
+}
+
+fn dot(a: &[Integer], b: &[Integer]) -> Integer {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| Integer::from(x * y))
+        .sum()
+}
+
+fn approx_dot(a: &[Approx], b: &[Approx]) -> Approx {
+    a.iter()
+        .zip(b.iter())
+        .fold(Approx::ZERO, |sum, (x, y)| sum.add(x.mul(*y)))
+}
+
+// Row k of r_kj = <b_k, b*_j> and mu_kj = r_kj / r_jj, from the rows before it
+fn gram_schmidt_row(
+    k: usize,
+    rows: &[Vec<Integer>],
+    approx: &[Vec<Approx>],
+    r: &mut [Vec<Approx>],
+    mu: &mut [Vec<Approx>],
+) {
+    let norm = approx_dot(&approx[k], &approx[k]);
+    for j in 0..=k {
+        let mut x = approx_dot(&approx[k], &approx[j]);
+        // Fewer than 60 bits of <b_k, b_j> survived, |<b_k, b_j>|^2 << |b_k|^2 |b_j|^2
+        let bound = norm.exponent + approx_dot(&approx[j], &approx[j]).exponent - 60;
+        if x.mantissa == 0.0 || 2 * x.exponent < bound {
+            x = Approx::from_integer(&dot(&rows[k], &rows[j]));
+        }
+        for i in 0..j {
+            x = x.sub(mu[j][i].mul(r[k][i]));
+        }
+        r[k][j] = x;
+        if j < k {
+            mu[k][j] = x.div(r[j][j]);
+        }
+    }
+}
+
+// LLL reduce linearly independent rows
+fn reduce_rows(rows: &[Vec<Integer>]) -> Vec<Vec<Integer>> {
+    let n = rows.len();
+    let mut rows = rows.to_vec();
+    let to_approx =
+        |row: &[Integer]| -> Vec<Approx> { row.iter().map(Approx::from_integer).collect() };
+    let mut approx: Vec<Vec<Approx>> = rows.iter().map(|row| to_approx(row)).collect();
+    let mut r = vec![vec![Approx::ZERO; n]; n];
+    let mut mu = vec![vec![Approx::ZERO; n]; n];
+
+    gram_schmidt_row(0, &rows, &approx, &mut r, &mut mu);
+    let mut k = 1;
+    while k < n {
+        // (P1) for row k, repeated until the rounded mu_kj stop changing b_k
+        loop {
+            gram_schmidt_row(k, &rows, &approx, &mut r, &mut mu);
+            let mut changed = false;
+            for j in (0..k).rev() {
+                if !mu[k][j].exceeds(SIZE_REDUCTION_ETA) {
+                    continue;
+                }
+                let q = mu[k][j].round();
+                if q == 0 {
+                    continue;
+                }
+                changed = true;
+                let (before, after) = rows.split_at_mut(k);
+                for (x, y) in after[0].iter_mut().zip(before[j].iter()) {
+                    *x -= &q * y;
+                }
+                let q = Approx::from_integer(&q);
+                let (before, after) = mu.split_at_mut(k);
+                for (x, y) in after[0].iter_mut().zip(before[j].iter()).take(j) {
+                    *x = x.sub(q.mul(*y));
+                }
+                after[0][j] = after[0][j].sub(q);
+            }
+            if !changed {
+                break;
+            }
+            approx[k] = to_approx(&rows[k]);
+        }
+
+        // (P2) for row k
+        let mu_squared = mu[k][k - 1].mul(mu[k][k - 1]);
+        let bound = r[k - 1][k - 1].mul(Approx::new(LOVASZ_DELTA, 0).sub(mu_squared));
+        if r[k][k].sub(bound).mantissa >= 0.0 {
+            k += 1;
+        } else {
+            rows.swap(k, k - 1);
+            approx.swap(k, k - 1);
+            if k > 1 {
+                k -= 1;
+            } else {
+                gram_schmidt_row(0, &rows, &approx, &mut r, &mut mu);
+            }
+        }
+    }
+    rows
+}
+
+// Some x in [lo, hi] with f(x) = 0, or with f(x) and f(x + 1) of opposite signs.
+// Binary search, so f must change sign at most once on [lo, hi]
+fn sign_change(f: &[Integer], lo: &Integer, hi: &Integer) -> Option<Integer> {
+    let (mut lo, mut hi) = (lo.clone(), hi.clone());
+    let sign_lo = evaluate(f, &lo).cmp0();
+    let sign_hi = evaluate(f, &hi).cmp0();
+    if sign_lo.is_eq() {
+        return Some(lo);
+    }
+    if sign_hi.is_eq() {
+        return Some(hi);
+    }
+    if sign_lo == sign_hi {
+        return None;
+    }
+
+    while Integer::from(&hi - &lo) > 1 {
+        let middle = Integer::from(&lo + &hi) >> 1;
+        let sign = evaluate(f, &middle).cmp0();
+        if sign.is_eq() {
+            return Some(middle);
+        }
+        if sign == sign_lo {
+            lo = middle;
+        } else {
+            hi = middle;
+        }
+    }
+    Some(lo)
+}
+
+// Sorted integer points splitting [lo, hi] into intervals on which f is monotone.
+// f' changes sign at most once between consecutive breakpoints of f', and f can only turn
+// around at those sign changes
+fn monotone_breakpoints(f: &[Integer], lo: &Integer, hi: &Integer) -> Vec<Integer> {
+    let mut points = vec![lo.clone(), hi.clone()];
+    if f.len() > 2 {
+        let df = derivative(f);
+        for window in monotone_breakpoints(&df, lo, hi).windows(2) {
+            if let Some(x) = sign_change(&df, &window[0], &window[1]) {
+                if x < *hi {
+                    points.push(Integer::from(&x + 1));
+                }
+                points.push(x);
+            }
+        }
+    }
+    points.sort();
+    points.dedup();
+    points
+}
 
-We can therefore setup a polynomial f(x) = x^e - c, for which x must be a root.
-(P2) f(x) = x^e - c = 0
+// Integer roots of f in [-bound, bound]
+fn integer_roots(f: &[Integer], bound: &Integer) -> Vec<Integer> {
+    let f = trim(f.to_vec());
+    if f.len() < 2 {
+        return vec![];
+    }
+
+    let mut roots: Vec<Integer> = vec![];
+    for window in monotone_breakpoints(&f, &Integer::from(-bound), bound).windows(2) {
+        if let Some(x) = sign_change(&f, &window[0], &window[1]) {
+            if evaluate(&f, &x) == 0 {
+                roots.push(x);
+            }
+        }
+        if evaluate(&f, &window[1]) == 0 {
+            roots.push(window[1].clone());
+        }
+    }
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+// COPPERSMITH / HOWGRAVE-GRAHAM SMALL ROOTS
+
+/*
+Let f(x) be a polynomial of degree d with f(x0) = 0 mod b, for an unknown divisor b >= N^beta of
+N and |x0| < X. Modular roots are hard to find, roots over the integers are easy (binary search).
+
+Every polynomial of the form
+(P1) g_ij(x) = x^j N^(m - i) f(x)^i      for i < m, j < d
+(P2) h_i(x)  = x^i f(x)^m                for i < t
+also vanishes at x0 modulo b^m. So does every integer combination of them.
+
+If a combination h has small coefficients, |h(x0)| < b^m and h(x0) = 0 mod b^m force h(x0) = 0
+over the integers (Howgrave-Graham). Writing each polynomial as the coefficient vector of g(xX),
+short vectors of this lattice are exactly such small combinations.
 
-We can find more small polynomials based on this root, LLL reduce their coefficients, and extract the root then.
+With m = ceil(beta^2 / (d epsilon)) and t = floor(d m (1 / beta - 1)), LLL finds one for every
+X <= N^(beta^2 / d - epsilon), up to its 2^((n - 1) / 4) approximation factor. To cover that
+factor, m is taken from epsilon / 2 (at least MIN_EPSILON).
+That m is then capped so the lattice has at most MAX_DIMENSION rows, and the capped lattice is
+still reduced. A capped m reaches a smaller X, so roots close to N^(beta^2 / d) can be missed.
 
 Algorithm:
 1. Construct polynomials.
@@ -27,28 +386,1098 @@ Algorithm:
 
 */
 
-pub fn low_public_exponent_attack(n: &Integer, e: usize, c: &Integer) -> Option<Integer> {
-    // Step 1: Define the polynomial f(x) = x^e - c
-    let mut x = Integer::new();
-    x.assign(0);
+const MIN_EPSILON: f64 = 0.005;
+// Reduction time grows steeply with the dimension, a 36-row lattice over a 1024-bit N with
+// e = 3 already takes about a minute
+const MAX_DIMENSION: usize = 40;
+
+pub fn small_roots(
+    f: &[Integer],
+    modulus: &Integer,
+    bound: &Integer,
+    beta: f64,
+    epsilon: f64,
+) -> SmallRoots {
+    // Make f monic modulo N
+    let f = trim(f.to_vec());
+    let degree = f.len() - 1;
+    let inverse = match f[degree].clone().invert(modulus) {
+        Ok(inverse) => inverse,
+        Err(_) => {
+            return SmallRoots {
+                roots: vec![],
+                dimension: 0,
+            }
+        }
+    };
+    let f: Polynomial = f
+        .iter()
+        .map(|coefficient| Integer::from(coefficient * &inverse).rem_euc(modulus))
+        .collect();
 
-    // Step 2: Brute-force small roots
-    // Increment x and check if x^e == c mod N
-    loop {
-        let candidate = x.clone().pow(e as u32) % n;
+    let epsilon = (epsilon / 2.0).max(MIN_EPSILON);
+    let extra_shifts = |m: usize| (degree as f64 * m as f64 * (1.0 / beta - 1.0)).floor() as usize;
+    let mut m = (beta * beta / (degree as f64 * epsilon)).ceil() as usize;
+    while m > 1 && degree * m + extra_shifts(m) > MAX_DIMENSION {
+        m -= 1;
+    }
+    let t = extra_shifts(m);
+    let dimension = degree * m + t;
 
-        if candidate == *c {
-            return Some(x); // Found the root
+    // Step 1: Construct polynomials
+    let mut shifts: Vec<Polynomial> = vec![];
+    for i in 0..m {
+        let power = poly_mul(&poly_pow(&f, i), &[modulus.clone().pow((m - i) as u32)]);
+        for j in 0..degree {
+            let mut shift = vec![Integer::new(); j];
+            shift.extend(power.iter().cloned());
+            shifts.push(shift);
         }
+    }
+    let power = poly_pow(&f, m);
+    for i in 0..t {
+        let mut shift = vec![Integer::new(); i];
+        shift.extend(power.iter().cloned());
+        shifts.push(shift);
+    }
 
-        x += 1;
+    // Step 2: Generate basis, coefficient k of g(xX) is g_k X^k
+    let scale: Vec<Integer> = (0..dimension)
+        .map(|k| bound.clone().pow(k as u32))
+        .collect();
+    let rows: Vec<Vec<Integer>> = shifts
+        .iter()
+        .map(|shift| {
+            (0..dimension)
+                .map(|k| match shift.get(k) {
+                    Some(coefficient) => Integer::from(coefficient * &scale[k]),
+                    None => Integer::new(),
+                })
+                .collect()
+        })
+        .collect();
 
-        // Break condition if we exceed N^(1/e) (for efficiency)
-        let limit = n.clone().root(e as u32);
-        if x > limit {
+    // Step 3: LLL reduce basis
+    let reduced = reduce_rows(&rows);
+
+    // Step 4: Extract roots from reduced polynomials, keep those with a large gcd(f(x0), N).
+    // The shortest vectors come first, stop at the first one that gives a root
+    let mut roots: Vec<Integer> = vec![];
+    for row in reduced.iter() {
+        if !roots.is_empty() {
             break;
         }
+        let h: Polynomial = row
+            .iter()
+            .zip(scale.iter())
+            .map(|(coefficient, power)| coefficient.clone().div_exact(power))
+            .collect();
+        for root in integer_roots(&h, bound) {
+            let divisor = evaluate(&f, &root).gcd(modulus);
+            if divisor.significant_bits() as f64 >= beta * modulus.significant_bits() as f64 - 1.0
+                && !roots.contains(&root)
+            {
+                roots.push(root);
+            }
+        }
     }
 
-    None // No root found
+    SmallRoots { roots, dimension }
+}
+
+// LOW EXPONENT ATTACK
+
+/*
+If the exponent is small, then the modulo N operation does not wrap around the result in c = m^e mod N
+(P1) So, we can safely say c = m^e.
+
+The root of f(x) = x^e - c is then an integer e-th root, no lattice is needed.
+*/
+
+pub fn low_public_exponent_attack(n: &Integer, e: usize, c: &Integer) -> Option<Integer> {
+    let (root, remainder) = c.clone().root_rem(Integer::new(), e as u32);
+    if remainder == 0 && c < n {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+// HASTAD BROADCAST ATTACK
+
+/*
+The same message is sent to several recipients with the same small e and pairwise coprime moduli.
+(P1) c_i = m^e mod N_i
+With at least e ciphertexts, CRT gives C = m^e mod N_1 ... N_k with m^e < N_1 ... N_k, so m is
+again an integer e-th root.
+
+If each recipient gets a known linear padding m_i = a_i m + b_i, the polynomials
+(P2) g_i(x) = (a_i x + b_i)^e - c_i = 0 mod N_i
+combine into g(x) = sum T_i g_i(x) mod N_1 ... N_k, with T_i = 1 mod N_i and T_i = 0 mod N_j.
+m is then a small root of g, found for m < (N_1 ... N_k)^(1/e - epsilon) while the lattice of
+small_roots fits MAX_DIMENSION.
+*/
+
+// CRT coefficients T_i. None unless the moduli are pairwise coprime
+fn crt_basis(moduli: &[Integer]) -> Option<Vec<Integer>> {
+    let product: Integer = moduli.iter().product();
+    moduli
+        .iter()
+        .map(|n_i| {
+            let rest = Integer::from(&product / n_i);
+            let inverse = rest.clone().invert(n_i).ok()?;
+            Some(rest * inverse)
+        })
+        .collect()
+}
+
+pub fn hastad_broadcast_attack(
+    e: usize,
+    ciphertexts: &[Integer],
+    moduli: &[Integer],
+) -> Option<Integer> {
+    if ciphertexts.len() != moduli.len() {
+        return None;
+    }
+    let basis = crt_basis(moduli)?;
+    let product: Integer = moduli.iter().product();
+    let combined: Integer = ciphertexts
+        .iter()
+        .zip(basis.iter())
+        .map(|(c, t)| Integer::from(c * t))
+        .sum();
+
+    low_public_exponent_attack(&product, e, &combined.rem_euc(&product))
+}
+
+// paddings[i] = (a_i, b_i), and m has at most message_bits bits
+pub fn hastad_padding_attack(
+    e: usize,
+    ciphertexts: &[Integer],
+    moduli: &[Integer],
+    paddings: &[(Integer, Integer)],
+    message_bits: u32,
+) -> Option<Integer> {
+    if ciphertexts.len() != moduli.len() || paddings.len() != moduli.len() {
+        return None;
+    }
+    let basis = crt_basis(moduli)?;
+    let product: Integer = moduli.iter().product();
+    let epsilon = 1.0 / e as f64 - message_bits as f64 / product.significant_bits() as f64;
+    if epsilon <= 0.0 {
+        return None;
+    }
+
+    let mut g: Polynomial = vec![Integer::new(); e + 1];
+    for ((c, (a, b)), t) in ciphertexts.iter().zip(paddings.iter()).zip(basis.iter()) {
+        let mut g_i = poly_pow(&[b.clone(), a.clone()], e);
+        g_i[0] -= c;
+        for (k, coefficient) in g_i.iter().enumerate() {
+            g[k] += coefficient * t;
+        }
+    }
+    let g = poly_mod(&g, &product);
+
+    let bound = Integer::from(1) << message_bits;
+    small_roots(&g, &product, &bound, 1.0, epsilon)
+        .roots
+        .into_iter()
+        .filter(|m| m.cmp0().is_ge())
+        .find(|m| {
+            ciphertexts
+                .iter()
+                .zip(paddings.iter())
+                .zip(moduli.iter())
+                .all(|((c, (a, b)), n_i)| {
+                    let padded = Integer::from(a * m) + b;
+                    padded.pow_mod(&Integer::from(e), n_i).ok().as_ref() == Some(c)
+                })
+        })
+}
+
+// STEREOTYPED MESSAGE ATTACK
+
+/*
+The message is known except for its lowest `unknown_bits` bits: m = known + x, 0 <= x < 2^unknown_bits.
+(P1) f(x) = (known + x)^e - c = 0 mod N
+
+x is a small root of a monic polynomial of degree e modulo N, found for x < N^(1/e - epsilon)
+while the lattice of small_roots fits MAX_DIMENSION. We spend whatever margin is left between
+2^unknown_bits and N^(1/e) as epsilon. A thin margin hits the cap, and the attack can then fail.
+*/
+
+pub fn stereotyped_message_attack(
+    n: &Integer,
+    e: usize,
+    c: &Integer,
+    known: &Integer,
+    unknown_bits: u32,
+) -> Option<Integer> {
+    let epsilon = 1.0 / e as f64 - unknown_bits as f64 / n.significant_bits() as f64;
+    if epsilon <= 0.0 {
+        return None;
+    }
+
+    let bound = Integer::from(1) << unknown_bits;
+    let f = shifted_power(known, e, c);
+    small_roots(&f, n, &bound, 1.0, epsilon)
+        .roots
+        .into_iter()
+        .filter(|x| x.cmp0().is_ge())
+        .map(|x| x + known)
+        .find(|m| m.clone().pow_mod(&Integer::from(e), n).ok().as_ref() == Some(c))
+}
+
+// PARTIAL KEY EXPOSURE ATTACK
+
+/*
+Part of the bits of p leak, either the top or the bottom ones. The rest is a small root of a linear
+polynomial modulo the unknown divisor p ~ N^(1/2) of N:
+(P1) most significant:  f(x) = known * 2^k + x            with k = unknown bits
+(P2) least significant: f(x) = known + 2^k * x            with k = known bits
+
+So beta = 1/2, d = 1 and x < N^(1/4 - epsilon): knowing a little more than half of p factors N,
+as long as the lattice fits MAX_DIMENSION.
+*/
+
+// Which end of p is known
+#[derive(Debug, Clone, Copy)]
+pub enum Exposure {
+    MostSignificant,
+    LeastSignificant,
+}
+
+// The factors if the attack succeeded, and the dimension of the lattice that was reduced
+pub struct Factorization {
+    pub factors: Option<(Integer, Integer)>,
+    pub dimension: usize,
+}
+
+// `known` holds the `known_bits` exposed bits of p, shifted down to bit 0.
+// p is assumed to have half the bits of N, as for keys generated with balanced primes
+pub fn partial_key_exposure_attack(
+    n: &Integer,
+    known: &Integer,
+    known_bits: u32,
+    exposure: Exposure,
+) -> Factorization {
+    let modulus_bits = n.significant_bits();
+    let prime_bits = modulus_bits.div_ceil(2);
+    // At most prime_bits - 1 bits of p can be known, otherwise there is nothing to find
+    if known_bits >= prime_bits {
+        return Factorization {
+            factors: None,
+            dimension: 0,
+        };
+    }
+    let unknown_bits = prime_bits - known_bits;
+
+    // p >= 2^(prime_bits - 1) = N^beta
+    let beta = (prime_bits - 1) as f64 / modulus_bits as f64;
+    let epsilon = beta * beta - unknown_bits as f64 / modulus_bits as f64;
+    if epsilon <= 0.0 {
+        return Factorization {
+            factors: None,
+            dimension: 0,
+        };
+    }
+
+    let f: Polynomial = match exposure {
+        Exposure::MostSignificant => vec![Integer::from(known << unknown_bits), Integer::from(1)],
+        Exposure::LeastSignificant => vec![known.clone(), Integer::from(1) << known_bits],
+    };
+    let bound = Integer::from(1) << unknown_bits;
+    let result = small_roots(&f, n, &bound, beta, epsilon);
+
+    let factors = result
+        .roots
+        .iter()
+        .map(|x| evaluate(&f, x))
+        .find(|p| *p > 1 && p < n && n.is_divisible(p))
+        .map(|p| {
+            let q = Integer::from(n / &p);
+            (p, q)
+        });
+
+    Factorization {
+        factors,
+        dimension: result.dimension,
+    }
+}
+
+// POLYNOMIALS OVER Z_N
+
+// Coefficients reduced into [0, N), leading zeros dropped
+fn poly_mod(f: &[Integer], n: &Integer) -> Polynomial {
+    trim(f.iter().map(|c| c.clone().rem_euc(n)).collect())
+}
+
+fn is_zero(f: &[Integer]) -> bool {
+    f.iter().all(|c| *c == 0)
+}
+
+// Remainder of a / b over Z_N. None if the leading coefficient of b is not invertible,
+// which would reveal a factor of N
+fn poly_rem_mod(a: &[Integer], b: &[Integer], n: &Integer) -> Option<Polynomial> {
+    let b = poly_mod(b, n);
+    let inverse = b[b.len() - 1].clone().invert(n).ok()?;
+
+    let mut r = poly_mod(a, n);
+    while r.len() >= b.len() && !is_zero(&r) {
+        let shift = r.len() - b.len();
+        let factor = Integer::from(&r[r.len() - 1] * &inverse);
+        for (i, c) in b.iter().enumerate() {
+            r[shift + i] -= &factor * c;
+        }
+        r = poly_mod(&r, n);
+    }
+    Some(r)
+}
+
+// Monic gcd over Z_N by Euclid's algorithm
+fn poly_gcd_mod(a: &[Integer], b: &[Integer], n: &Integer) -> Option<Polynomial> {
+    let (mut a, mut b) = (poly_mod(a, n), poly_mod(b, n));
+    while !is_zero(&b) {
+        let r = poly_rem_mod(&a, &b, n)?;
+        a = b;
+        b = r;
+    }
+
+    let inverse = a[a.len() - 1].clone().invert(n).ok()?;
+    Some(
+        a.iter()
+            .map(|c| Integer::from(c * &inverse).rem_euc(n))
+            .collect(),
+    )
+}
+
+// Determinant over Z_N by Gaussian elimination
+fn determinant_mod(mut rows: Vec<Vec<Integer>>, n: &Integer) -> Option<Integer> {
+    let size = rows.len();
+    let mut determinant = Integer::from(1);
+    for col in 0..size {
+        if (col..size).all(|r| rows[r][col] == 0) {
+            return Some(Integer::new());
+        }
+        let pivot = (col..size).find(|&r| rows[r][col].clone().invert(n).is_ok())?;
+        if pivot != col {
+            rows.swap(pivot, col);
+            determinant = -determinant;
+        }
+
+        let inverse = rows[col][col].clone().invert(n).unwrap();
+        determinant = Integer::from(&determinant * &rows[col][col]).rem_euc(n);
+        for r in col + 1..size {
+            let factor = Integer::from(&rows[r][col] * &inverse).rem_euc(n);
+            for c in col..size {
+                let product = Integer::from(&factor * &rows[col][c]);
+                rows[r][c] = Integer::from(&rows[r][c] - &product).rem_euc(n);
+            }
+        }
+    }
+    Some(determinant.rem_euc(n))
+}
+
+// Sylvester matrix of a and b. They keep their formal degree, so evaluations of a resultant with
+// polynomial coefficients stay consistent
+fn sylvester(a: &[Integer], b: &[Integer]) -> Vec<Vec<Integer>> {
+    let (da, db) = (a.len() - 1, b.len() - 1);
+    let size = da + db;
+    let mut rows: Vec<Vec<Integer>> = vec![];
+    for (shifts, f) in [(db, a), (da, b)] {
+        for i in 0..shifts {
+            let mut row = vec![Integer::new(); size];
+            for (k, c) in f.iter().rev().enumerate() {
+                row[i + k] = c.clone();
+            }
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+// Resultant over Z_N as the determinant of the Sylvester matrix
+fn resultant_mod(a: &[Integer], b: &[Integer], n: &Integer) -> Option<Integer> {
+    let rows = sylvester(a, b)
+        .into_iter()
+        .map(|row| row.into_iter().map(|c| c.rem_euc(n)).collect())
+        .collect();
+    determinant_mod(rows, n)
+}
+
+// The polynomial of degree < values.len() through (i, values[i]) over Z_N, by Lagrange
+fn interpolate_mod(values: &[Integer], n: &Integer) -> Option<Polynomial> {
+    let count = values.len();
+    let mut result: Polynomial = vec![Integer::new(); count];
+    for i in 0..count {
+        let mut basis: Polynomial = vec![Integer::from(1)];
+        let mut denominator = Integer::from(1);
+        for j in (0..count).filter(|&j| j != i) {
+            basis = poly_mul(&basis, &[Integer::from(-(j as i64)), Integer::from(1)]);
+            denominator *= Integer::from(i as i64 - j as i64);
+        }
+
+        let scale = Integer::from(&values[i] * &denominator.invert(n).ok()?);
+        for (k, c) in basis.iter().enumerate() {
+            result[k] += c * &scale;
+        }
+    }
+    Some(poly_mod(&result, n))
+}
+
+// FRANKLIN-REITER RELATED MESSAGE ATTACK
+
+/*
+Two messages related by a known affine map, m2 = a m1 + b, encrypted under the same (N, e).
+m1 is a common root of
+(P1) g1(x) = x^e - c1
+(P2) g2(x) = (a x + b)^e - c2
+and for small e their gcd over Z_N is almost always exactly x - m1.
+*/
+
+pub fn franklin_reiter_attack(
+    n: &Integer,
+    e: usize,
+    c1: &Integer,
+    c2: &Integer,
+    a: &Integer,
+    b: &Integer,
+) -> Option<Integer> {
+    let g1 = shifted_power(&Integer::new(), e, c1);
+    let mut g2 = poly_pow(&[b.clone(), a.clone()], e);
+    g2[0] -= c2;
+
+    let gcd = poly_gcd_mod(&g1, &g2, n)?;
+    if gcd.len() != 2 {
+        return None;
+    }
+    Some(Integer::from(-&gcd[0]).rem_euc(n))
+}
+
+// COPPERSMITH SHORT PAD ATTACK
+
+/*
+The same message is encrypted twice with different random paddings of `pad_bits` bits:
+m1 = M 2^k + r1, m2 = M 2^k + r2, so m2 = m1 + y for a small y = r2 - r1.
+
+Eliminating x from g1(x) = x^e - c1 and g2(x, y) = (x + y)^e - c2 gives
+(P1) h(y) = Res_x(g1, g2) = 0 mod N
+of degree e^2 in y. We get h by evaluating the resultant at e^2 + 1 points and interpolating.
+y is a small root of h when pad_bits < log(N) / e^2, less what the MAX_DIMENSION cap of
+small_roots costs, and Franklin-Reiter with m2 = m1 + y recovers both messages.
+*/
+
+pub fn short_pad_attack(
+    n: &Integer,
+    e: usize,
+    c1: &Integer,
+    c2: &Integer,
+    pad_bits: u32,
+) -> Option<(Integer, Integer)> {
+    let epsilon = 1.0 / (e * e) as f64 - pad_bits as f64 / n.significant_bits() as f64;
+    if epsilon <= 0.0 {
+        return None;
+    }
+
+    let g1 = shifted_power(&Integer::new(), e, c1);
+    let values = (0..=e * e)
+        .map(|y| resultant_mod(&g1, &shifted_power(&Integer::from(y), e, c2), n))
+        .collect::<Option<Vec<Integer>>>()?;
+    let h = interpolate_mod(&values, n)?;
+
+    let bound = Integer::from(1) << pad_bits;
+    small_roots(&h, n, &bound, 1.0, epsilon)
+        .roots
+        .iter()
+        .find_map(|y| {
+            let m1 = franklin_reiter_attack(n, e, c1, c2, &Integer::from(1), y)?;
+            let m2 = Integer::from(&m1 + y);
+            Some((m1, m2))
+        })
+}
+
+// SMALL PRIVATE EXPONENT ATTACKS
+
+/*
+A small d speeds up decryption, but ed = 1 + k phi(N) with k < d leaks it.
+(P1) e / N ~ k / d to within 1 / (2 d^2) when d < N^(1/4) / 3
+
+so k / d is a convergent of the continued fraction of e / N (Wiener). Each convergent gives a
+candidate phi = (ed - 1) / k and p + q = N + 1 - phi, checked by solving z^2 - (p + q) z + N = 0.
+*/
+
+// The private exponent and the factors of N
+pub struct RecoveredKey {
+    pub d: Integer,
+    pub p: Integer,
+    pub q: Integer,
+}
+
+// p and q from N and p + q
+fn factor_from_sum(n: &Integer, sum: &Integer) -> Option<(Integer, Integer)> {
+    let discriminant = Integer::from(sum * sum) - Integer::from(n << 2);
+    if discriminant.cmp0().is_lt() || !discriminant.is_perfect_square() {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let p = Integer::from(sum + &root) >> 1;
+    let q = Integer::from(sum - &root) >> 1;
+    if q > 1 && Integer::from(&p * &q) == *n {
+        Some((p, q))
+    } else {
+        None
+    }
+}
+
+fn recover_key(e: &Integer, p: Integer, q: Integer) -> Option<RecoveredKey> {
+    let phi = Integer::from(&p - 1) * Integer::from(&q - 1);
+    let d = e.clone().invert(&phi).ok()?;
+    Some(RecoveredKey { d, p, q })
+}
+
+pub fn wiener_attack(n: &Integer, e: &Integer) -> Option<RecoveredKey> {
+    // Convergents h / k of e / N, from h_-2 / k_-2 = 0 / 1 and h_-1 / k_-1 = 1 / 0
+    let (mut a, mut b) = (e.clone(), n.clone());
+    let (mut h0, mut h1) = (Integer::from(0), Integer::from(1));
+    let (mut k0, mut k1) = (Integer::from(1), Integer::from(0));
+    while b != 0 {
+        let (quotient, remainder) = a.div_rem(b.clone());
+        a = b;
+        b = remainder;
+
+        let h = Integer::from(&quotient * &h1) + &h0;
+        h0 = std::mem::replace(&mut h1, h);
+        let k = Integer::from(&quotient * &k1) + &k0;
+        k0 = std::mem::replace(&mut k1, k);
+
+        // h1 = k and k1 = d: phi = (e d - 1) / k
+        if h1 == 0 {
+            continue;
+        }
+        let numerator: Integer = Integer::from(e * &k1) - 1;
+        if !numerator.is_divisible(&h1) {
+            continue;
+        }
+        let phi = numerator.div_exact(&h1);
+        let sum = Integer::from(n - &phi) + 1;
+        if let Some((p, q)) = factor_from_sum(n, &sum) {
+            return recover_key(e, p, q);
+        }
+    }
+    None
+}
+
+// BONEH-DURFEE ATTACK
+
+/*
+Write phi(N) = N + 1 - (p + q) = 2 (A + y0) with A = (N + 1) / 2 and y0 = -(p + q) / 2.
+Then ed = 1 + 2k (A + y0), so (x0, y0) = (2k, y0) is a root of
+(P1) f(x, y) = 1 + x (A + y) = 0 mod e
+with |x0| < X = 2 N^delta and |y0| < Y = N^(1/2).
+
+Like the univariate case, every
+(P2) g_ik(x, y) = x^i f^k e^(m - k)        for k <= m, i <= m - k     (x-shifts)
+(P3) h_jk(x, y) = y^j f^k e^(m - k)        for k <= m, 1 <= j <= t    (y-shifts)
+vanishes at (x0, y0) modulo e^m. Ordered by their leading monomials x^(i + k) y^k and
+x^k y^(k + j) the basis is triangular, which gives delta < 0.284.
+
+y-shifts whose diagonal entry exceeds e^m add more to the determinant than to the dimension, so
+Boneh and Durfee keep only those with diagonal entry at most e^m. The kept rows span a sublattice
+of lower rank. Its basis is not square, the columns of the dropped leading monomials stay, so its
+determinant is no longer the product of the diagonal. Boneh and Durfee show the basis is
+geometrically progressive: entries shrink geometrically in the powers of X, Y and e away from the
+diagonal. The sublattice determinant is then still bounded by the product of the kept diagonal
+entries, up to a small factor, which pushes the bound towards 0.292. LLL only needs independent
+rows, so the sublattice basis is reduced as it is, without the columns no kept row touches.
+
+Two short vectors give two polynomials p1, p2 with p1(x0, y0) = p2(x0, y0) = 0 over the integers.
+y0 is then an integer root of the resultant Res_x(p1, p2), and p + q = -2 y0.
+*/
+
+// Polynomials in x and y, the coefficient of x^i y^j at [i][j]
+type Bivariate = Vec<Vec<Integer>>;
+
+fn bivariate_mul(a: &Bivariate, b: &Bivariate) -> Bivariate {
+    let width = |f: &Bivariate| f.iter().map(|row| row.len()).max().unwrap();
+    let mut product: Bivariate =
+        vec![vec![Integer::new(); width(a) + width(b) - 1]; a.len() + b.len() - 1];
+    for (i1, row1) in a.iter().enumerate() {
+        for (j1, x) in row1.iter().enumerate() {
+            for (i2, row2) in b.iter().enumerate() {
+                for (j2, y) in row2.iter().enumerate() {
+                    product[i1 + i2][j1 + j2] += x * y;
+                }
+            }
+        }
+    }
+    product
+}
+
+// f(x, v) as a polynomial in x
+fn evaluate_y(f: &Bivariate, v: &Integer) -> Polynomial {
+    f.iter().map(|row| evaluate(row, v)).collect()
+}
+
+// Determinant over Z by fraction-free (Bareiss) elimination
+fn determinant(mut rows: Vec<Vec<Integer>>) -> Integer {
+    let size = rows.len();
+    if size == 0 {
+        return Integer::from(1);
+    }
+
+    let mut negate = false;
+    let mut previous = Integer::from(1);
+    for col in 0..size - 1 {
+        let pivot = match (col..size).find(|&r| rows[r][col] != 0) {
+            Some(pivot) => pivot,
+            None => return Integer::new(),
+        };
+        if pivot != col {
+            rows.swap(pivot, col);
+            negate = !negate;
+        }
+
+        for r in col + 1..size {
+            for c in col + 1..size {
+                let value = Integer::from(&rows[r][c] * &rows[col][col])
+                    - Integer::from(&rows[r][col] * &rows[col][c]);
+                rows[r][c] = value.div_exact(&previous);
+            }
+        }
+        previous = rows[col][col].clone();
+    }
+
+    let determinant = rows[size - 1][size - 1].clone();
+    if negate {
+        -determinant
+    } else {
+        determinant
+    }
+}
+
+// The integer polynomial of degree < values.len() through (i, values[i]), by Newton's forward
+// differences. With integer coefficients the k-th difference is divisible by k!
+fn interpolate(values: &[Integer]) -> Polynomial {
+    let mut differences = values.to_vec();
+    let mut result: Polynomial = vec![Integer::new(); values.len()];
+    let mut falling: Polynomial = vec![Integer::from(1)]; // y (y - 1) ... (y - k + 1)
+    let mut factorial = Integer::from(1);
+    for k in 0..values.len() {
+        let coefficient = differences[0].clone().div_exact(&factorial);
+        for (i, c) in falling.iter().enumerate() {
+            result[i] += c * &coefficient;
+        }
+
+        differences = differences
+            .windows(2)
+            .map(|w| Integer::from(&w[1] - &w[0]))
+            .collect();
+        falling = poly_mul(&falling, &[Integer::from(-(k as i64)), Integer::from(1)]);
+        factorial *= (k + 1) as u32;
+    }
+    trim(result)
+}
+
+// Res_x(a, b) as a polynomial in y, interpolated from its values at y = 0, 1, ...
+fn resultant_x(a: &Bivariate, b: &Bivariate) -> Polynomial {
+    let degree_y = |f: &Bivariate| f.iter().map(|row| row.len() - 1).max().unwrap();
+    let (da, db) = (a.len() - 1, b.len() - 1);
+    let degree = da * degree_y(b) + db * degree_y(a);
+
+    let values: Vec<Integer> = (0..=degree)
+        .map(|v| {
+            let v = Integer::from(v);
+            determinant(sylvester(&evaluate_y(a, &v), &evaluate_y(b, &v)))
+        })
+        .collect();
+    interpolate(&values)
+}
+
+// Bivariate polynomial of a reduced row, undoing the X^i Y^j scaling. Zero x-rows are dropped
+fn row_polynomial(
+    row: &[Integer],
+    monomials: &[(usize, usize)],
+    x_powers: &[Integer],
+    y_powers: &[Integer],
+) -> Bivariate {
+    let degree_x = monomials.iter().map(|&(i, _)| i).max().unwrap();
+    let degree_y = monomials.iter().map(|&(_, j)| j).max().unwrap();
+    let mut f: Bivariate = vec![vec![Integer::new(); degree_y + 1]; degree_x + 1];
+    for (value, &(i, j)) in row.iter().zip(monomials.iter()) {
+        f[i][j] = value
+            .clone()
+            .div_exact(&x_powers[i])
+            .div_exact(&y_powers[j]);
+    }
+    while f.len() > 1 && is_zero(&f[f.len() - 1]) {
+        f.pop();
+    }
+    f
+}
+
+// d < N^delta, m controls the lattice size. t = (1 - 2 delta) m y-shifts are optimal
+pub fn boneh_durfee_attack(n: &Integer, e: &Integer, delta: f64, m: usize) -> Option<RecoveredKey> {
+    let bits = n.significant_bits();
+    let t = ((1.0 - 2.0 * delta) * m as f64).floor() as usize;
+    let a = Integer::from(n + 1) >> 1;
+    let x_bound = Integer::from(1) << ((delta * bits as f64).ceil() as u32 + 1);
+    let y_bound = Integer::from(1) << bits.div_ceil(2);
+
+    // f(x, y) = 1 + A x + x y and its powers
+    let f: Bivariate = vec![vec![Integer::from(1)], vec![a, Integer::from(1)]];
+    let mut powers: Vec<Bivariate> = vec![vec![vec![Integer::from(1)]]];
+    for k in 1..=m {
+        powers.push(bivariate_mul(&powers[k - 1], &f));
+    }
+
+    // Step 1: Construct polynomials, (i, j, k) stands for x^i y^j f^k e^(m - k)
+    let mut shifts: Vec<(usize, usize, usize)> = vec![];
+    for k in 0..=m {
+        for i in 0..=m - k {
+            shifts.push((i, 0, k));
+        }
+    }
+    for j in 1..=t {
+        for k in 0..=m {
+            shifts.push((0, j, k));
+        }
+    }
+    let monomials: Vec<(usize, usize)> = shifts.iter().map(|&(i, j, k)| (i + k, j + k)).collect();
+
+    // Step 2: Generate basis, coefficient of x^i y^j scaled by X^i Y^j
+    let x_powers: Vec<Integer> = (0..=m).map(|i| x_bound.clone().pow(i as u32)).collect();
+    let y_powers: Vec<Integer> = (0..=m + t).map(|j| y_bound.clone().pow(j as u32)).collect();
+    let e_powers: Vec<Integer> = (0..=m).map(|k| e.clone().pow(k as u32)).collect();
+
+    let mut rows: Vec<Vec<Integer>> = vec![];
+    for &(i, j, k) in shifts.iter() {
+        let diagonal = Integer::from(&e_powers[m - k] * &x_powers[i + k]) * &y_powers[j + k];
+        if j > 0 && diagonal > e_powers[m] {
+            continue;
+        }
+
+        let mut row = vec![Integer::new(); monomials.len()];
+        for (a, coefficients) in powers[k].iter().enumerate() {
+            for (b, coefficient) in coefficients.iter().enumerate() {
+                if *coefficient == 0 {
+                    continue;
+                }
+                let column = monomials
+                    .iter()
+                    .position(|&monomial| monomial == (a + i, b + j))
+                    .unwrap();
+                row[column] = Integer::from(coefficient * &e_powers[m - k])
+                    * &x_powers[a + i]
+                    * &y_powers[b + j];
+            }
+        }
+        rows.push(row);
+    }
+    let used: Vec<usize> = (0..monomials.len())
+        .filter(|&column| rows.iter().any(|row| row[column] != 0))
+        .collect();
+    let rows: Vec<Vec<Integer>> = rows
+        .iter()
+        .map(|row| used.iter().map(|&column| row[column].clone()).collect())
+        .collect();
+    let monomials: Vec<(usize, usize)> = used.iter().map(|&column| monomials[column]).collect();
+
+    // Step 3: LLL reduce basis
+    let reduced = reduce_rows(&rows);
+    let polynomials: Vec<Bivariate> = reduced
+        .iter()
+        .map(|row| row_polynomial(row, &monomials, &x_powers, &y_powers))
+        .collect();
+
+    // Step 4: Eliminate x from pairs of short polynomials, y0 is a root of the resultant
+    for second in 1..polynomials.len().min(4) {
+        for first in 0..second {
+            let resultant = resultant_x(&polynomials[first], &polynomials[second]);
+            if is_zero(&resultant) {
+                continue;
+            }
+            for y in integer_roots(&resultant, &y_bound) {
+                let sum = (-y) << 1;
+                if let Some((p, q)) = factor_from_sum(n, &sum) {
+                    return recover_key(e, p, q);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rug::rand::RandState;
+
+    // Exact d_i = |b*_0|^2 ... |b*_(i-1)|^2 and lambda_ij = d_(j+1) mu_ij (Cohen, Algorithm 2.6.7)
+    fn integral_gram_schmidt(rows: &[Vec<Integer>]) -> (Vec<Integer>, Vec<Vec<Integer>>) {
+        let n = rows.len();
+        let mut d = vec![Integer::from(1); n + 1];
+        let mut lambda = vec![vec![Integer::new(); n]; n];
+        for k in 0..n {
+            for j in 0..=k {
+                let mut u = dot(&rows[k], &rows[j]);
+                for i in 0..j {
+                    u = Integer::from(&d[i + 1] * &u)
+                        - Integer::from(&lambda[k][i] * &lambda[j][i]);
+                    u = u.div_exact(&d[i]);
+                }
+                if j < k {
+                    lambda[k][j] = u;
+                } else {
+                    d[k + 1] = u;
+                }
+            }
+        }
+        (d, lambda)
+    }
+
+    // |mu_kj| <= 51/100 and |b*_k|^2 >= (98/100 - mu_k(k-1)^2) |b*_(k-1)|^2, in exact arithmetic
+    fn assert_lll_reduced(rows: &[Vec<Integer>]) {
+        let (d, lambda) = integral_gram_schmidt(rows);
+        for k in 0..rows.len() {
+            for j in 0..k {
+                let size = lambda[k][j].clone().abs() * 100u32;
+                assert!(
+                    size <= Integer::from(&d[j + 1] * 51u32),
+                    "row {} is not size reduced",
+                    k
+                );
+            }
+            if k > 0 {
+                let lhs = Integer::from(&d[k + 1] * &d[k - 1]) * 100u32;
+                let rhs = Integer::from(&d[k] * &d[k]) * 98u32
+                    - Integer::from(&lambda[k][k - 1] * &lambda[k][k - 1]) * 100u32;
+                assert!(
+                    lhs >= rhs,
+                    "rows {} and {} fail the Lovasz condition",
+                    k - 1,
+                    k
+                );
+            }
+        }
+    }
+
+    // RSA modulus N = pq of `bits` bits with gcd(e, phi(N)) = 1. Returns (N, p, q)
+    fn rsa_modulus(bits: u32, e: u32, state: &mut RandState) -> (Integer, Integer, Integer) {
+        let prime = |bits: u32, state: &mut RandState| {
+            let mut candidate = Integer::from(Integer::random_bits(bits, state));
+            candidate.set_bit(bits - 1, true);
+            candidate.next_prime()
+        };
+        loop {
+            let p = prime(bits / 2, state);
+            let q = prime(bits - bits / 2, state);
+            let phi = Integer::from(&p - 1) * Integer::from(&q - 1);
+            if p != q
+                && phi.gcd(&Integer::from(e)) == 1
+                && (Integer::from(&p * &q)).significant_bits() == bits
+            {
+                return (Integer::from(&p * &q), p, q);
+            }
+        }
+    }
+
+    // Stereotyped RSA message with its lowest `unknown_bits` bits unknown.
+    // Returns (N, c, known part, message)
+    fn stereotyped(
+        bits: u32,
+        e: u32,
+        unknown_bits: u32,
+        state: &mut RandState,
+    ) -> (Integer, Integer, Integer, Integer) {
+        let (n, _, _) = rsa_modulus(bits, e, state);
+        let message = Integer::from(Integer::random_bits(bits - 8, state));
+        let cipher_text = message.clone().pow_mod(&Integer::from(e), &n).unwrap();
+        let known = Integer::from(&message >> unknown_bits) << unknown_bits;
+        (n, cipher_text, known, message)
+    }
+
+    // RSA key with a random private exponent of exactly `d_bits` bits. Returns (N, e, d)
+    fn weak_rsa_key(bits: u32, d_bits: u32, state: &mut RandState) -> (Integer, Integer, Integer) {
+        loop {
+            let (n, p, q) = rsa_modulus(bits, 1, state);
+            let phi = Integer::from(&p - 1) * Integer::from(&q - 1);
+            let mut d = Integer::from(Integer::random_bits(d_bits, state));
+            d.set_bit(d_bits - 1, true);
+            if let Ok(e) = d.clone().invert(&phi) {
+                return (n, e, d);
+            }
+        }
+    }
+
+    fn assert_recovered(result: Option<RecoveredKey>, n: &Integer, d: &Integer) {
+        let key = result.expect("no key recovered");
+        assert_eq!(&key.d, d);
+        assert_eq!(&Integer::from(&key.p * &key.q), n);
+    }
+
+    // 60% of the bits of p factor N. 40% is below the N^(1/4) bound, no lattice is built
+    fn assert_partial_key_exposure(bits: u32, exposure: Exposure, seed: u32) {
+        let mut state = RandState::new();
+        state.seed(&Integer::from(seed));
+        let (n, p, _) = rsa_modulus(bits, 65537, &mut state);
+        let prime_bits = bits / 2;
+        let exposed = |known_bits: u32| match exposure {
+            Exposure::MostSignificant => Integer::from(&p >> (prime_bits - known_bits)),
+            Exposure::LeastSignificant => p.clone().keep_bits(known_bits),
+        };
+
+        let known_bits = prime_bits * 3 / 5;
+        let result = partial_key_exposure_attack(&n, &exposed(known_bits), known_bits, exposure);
+        assert!(result.dimension > 0);
+        assert!(matches!(result.factors, Some((x, y)) if x == p || y == p));
+
+        let known_bits = prime_bits * 2 / 5;
+        let result = partial_key_exposure_attack(&n, &exposed(known_bits), known_bits, exposure);
+        assert!(result.factors.is_none());
+        assert_eq!(result.dimension, 0);
+    }
+
+    fn integers(rows: &[&[i64]]) -> Vec<Vec<Integer>> {
+        rows.iter()
+            .map(|row| row.iter().map(|&x| Integer::from(x)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn reduces_against_first_row() {
+        let rows = integers(&[&[1, 0, 0], &[1000, 1, 0], &[1000, 1000, 1]]);
+        let reduced = reduce_rows(&rows);
+        assert_lll_reduced(&reduced);
+        assert_eq!(reduced, integers(&[&[1, 0, 0], &[0, 1, 0], &[0, 0, 1]]));
+    }
+
+    #[test]
+    fn reduces_random_lattice() {
+        let mut state = RandState::new();
+        state.seed(&Integer::from(2024));
+        let rows: Vec<Vec<Integer>> = (0..12)
+            .map(|_| {
+                (0..12)
+                    .map(|_| Integer::from(Integer::random_bits(400, &mut state)))
+                    .collect()
+            })
+            .collect();
+        let reduced = reduce_rows(&rows);
+        assert_lll_reduced(&reduced);
+        assert_eq!(determinant(reduced).abs(), determinant(rows).abs());
+    }
+
+    #[test]
+    fn finds_knapsack_solution() {
+        // Rows (2 e_i, a_i) and (1, ..., 1, s) with s a subset sum of the a_i. The subset gives a
+        // vector of +-1 and a final 0, far shorter than anything else in the lattice
+        let mut state = RandState::new();
+        state.seed(&Integer::from(7));
+        let n = 16;
+        let weights: Vec<Integer> = (0..n)
+            .map(|_| Integer::from(Integer::random_bits(2000, &mut state)))
+            .collect();
+        let target: Integer = weights.iter().step_by(2).sum();
+        let mut rows: Vec<Vec<Integer>> = (0..n)
+            .map(|i| {
+                let mut row = vec![Integer::new(); n + 1];
+                row[i] = Integer::from(2);
+                row[n] = weights[i].clone();
+                row
+            })
+            .collect();
+        let mut last = vec![Integer::from(1); n + 1];
+        last[n] = target;
+        rows.push(last);
+        let reduced = reduce_rows(&rows);
+        assert_lll_reduced(&reduced);
+        assert!(reduced[0][n] == 0 && reduced[0][..n].iter().all(|x| x.clone().abs() == 1));
+    }
+
+    #[test]
+    fn caps_the_lattice_dimension() {
+        // With e = 13, 5 unknown bits of 128 ask for m = 5 and 65 rows. m = 3 gives 39 rows, and
+        // still reaches N^(2/38), about 6.7 bits
+        let mut state = RandState::new();
+        state.seed(&Integer::from(11));
+        let (n, c, known, message) = stereotyped(128, 13, 5, &mut state);
+        let f = shifted_power(&known, 13, &c);
+        let epsilon = 1.0 / 13.0 - 5.0 / 128.0;
+        let result = small_roots(&f, &n, &Integer::from(32), 1.0, epsilon);
+        assert_eq!(result.dimension, 39);
+        assert!(result.roots.contains(&Integer::from(&message - &known)));
+    }
+
+    #[test]
+    fn partial_key_exposure_512_most_significant() {
+        assert_partial_key_exposure(512, Exposure::MostSignificant, 1);
+    }
+
+    #[test]
+    fn partial_key_exposure_512_least_significant() {
+        assert_partial_key_exposure(512, Exposure::LeastSignificant, 2);
+    }
+
+    #[test]
+    #[ignore = "1024-bit keys, slow in debug builds"]
+    fn partial_key_exposure_1024_most_significant() {
+        assert_partial_key_exposure(1024, Exposure::MostSignificant, 3);
+    }
+
+    #[test]
+    #[ignore = "1024-bit keys, slow in debug builds"]
+    fn partial_key_exposure_1024_least_significant() {
+        assert_partial_key_exposure(1024, Exposure::LeastSignificant, 4);
+    }
+
+    #[test]
+    #[ignore = "reduces a 39-row lattice over a 1024-bit N, minutes in release"]
+    fn stereotyped_message_beyond_the_cap() {
+        let mut state = RandState::new();
+        state.seed(&Integer::from(2024));
+        let (n, c, known, message) = stereotyped(1024, 3, 320, &mut state);
+        assert_eq!(
+            stereotyped_message_attack(&n, 3, &c, &known, 320),
+            Some(message)
+        );
+    }
+
+    #[test]
+    #[ignore = "reduces a 24-row lattice over a 2048-bit N, over a minute in release"]
+    fn stereotyped_message_2048() {
+        let mut state = RandState::new();
+        state.seed(&Integer::from(5));
+        let (n, c, known, message) = stereotyped(2048, 3, 512, &mut state);
+        assert_eq!(
+            stereotyped_message_attack(&n, 3, &c, &known, 512),
+            Some(message)
+        );
+    }
+
+    #[test]
+    fn wiener_recovers_small_d() {
+        let mut state = RandState::new();
+        state.seed(&Integer::from(6));
+        let (n, e, d) = weak_rsa_key(1024, 240, &mut state);
+        assert_recovered(wiener_attack(&n, &e), &n, &d);
+    }
+
+    #[test]
+    fn boneh_durfee_beyond_wiener() {
+        // d ~ N^0.26, past the N^0.25 of Wiener
+        let mut state = RandState::new();
+        state.seed(&Integer::from(7));
+        let (n, e, d) = weak_rsa_key(1024, 266, &mut state);
+        assert!(wiener_attack(&n, &e).is_none());
+        assert_recovered(boneh_durfee_attack(&n, &e, 0.26, 4), &n, &d);
+    }
+
+    #[test]
+    #[ignore = "reduces the m = 6 lattice, about two minutes in release"]
+    fn boneh_durfee_delta_027() {
+        let mut state = RandState::new();
+        state.seed(&Integer::from(8));
+        let (n, e, d) = weak_rsa_key(1024, 276, &mut state);
+        assert_recovered(boneh_durfee_attack(&n, &e, 0.27, 6), &n, &d);
+    }
 }
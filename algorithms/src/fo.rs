@@ -0,0 +1,99 @@
+// Fujisaki-Okamoto transform, in the Hofheinz-Hovelmanns-Kiltz form with implicit rejection.
+// Turns any CPA-secure scheme with derandomized encryption into an IND-CCA KEM:
+// encaps encrypts a random message m under coins G(m) and derives K = H(m || c).
+// Decaps decrypts, re-encrypts with the same coins and only accepts an identical ciphertext,
+// otherwise it returns the pseudorandom key H(z || c) for a secret z instead of an error.
+use rand::{CryptoRng, RngCore};
+use sha3::{Digest, Sha3_256};
+
+use crate::pke::{bits_to_bytes, bytes_to_bits, DeterministicEncryption, PublicKeyEncryption};
+
+// Domain separation between G, H on accepted ciphertexts and H on rejected ones
+const DOMAIN_COINS: u8 = 0;
+const DOMAIN_KEY: u8 = 1;
+const DOMAIN_REJECT: u8 = 2;
+
+pub struct SecretKey<S: PublicKeyEncryption> {
+    pub secret_key: S::SecretKey,
+    pub rejection_seed: [u8; 32], // z
+}
+
+fn hash(domain: u8, parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update([domain]);
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+pub fn keygen<S: DeterministicEncryption>(
+    params: &S::Params,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (S::PublicKey, SecretKey<S>) {
+    let (public_key, secret_key) = S::keygen(params, rng);
+
+    let mut rejection_seed = [0u8; 32];
+    rng.fill_bytes(&mut rejection_seed);
+
+    (
+        public_key,
+        SecretKey {
+            secret_key,
+            rejection_seed,
+        },
+    )
+}
+
+// Deterministic encapsulation of a chosen message of message_bits / 8 bytes.
+// Returns (shared key, ciphertext)
+pub fn encaps_internal<S: DeterministicEncryption>(
+    params: &S::Params,
+    public_key: &S::PublicKey,
+    message: &[u8],
+) -> ([u8; 32], S::Ciphertext) {
+    let coins = hash(DOMAIN_COINS, &[message]);
+    let cipher_text = S::encrypt_with_coins(params, public_key, &bytes_to_bits(message), &coins);
+    let key = hash(DOMAIN_KEY, &[message, &S::ciphertext_bytes(&cipher_text)]);
+    (key, cipher_text)
+}
+
+pub fn encaps<S: DeterministicEncryption>(
+    params: &S::Params,
+    public_key: &S::PublicKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> ([u8; 32], S::Ciphertext) {
+    let mut message: Vec<u8> = vec![0; S::message_bits(params) / 8];
+    rng.fill_bytes(&mut message);
+    encaps_internal::<S>(params, public_key, &message)
+}
+
+// Re-encryption needs the public key alongside the secret key
+pub fn decaps<S: DeterministicEncryption>(
+    params: &S::Params,
+    public_key: &S::PublicKey,
+    secret_key: &SecretKey<S>,
+    cipher_text: &S::Ciphertext,
+) -> [u8; 32] {
+    let message = bits_to_bytes(&S::decrypt(params, &secret_key.secret_key, cipher_text));
+    let (key, recomputed) = encaps_internal::<S>(params, public_key, &message);
+
+    let bytes = S::ciphertext_bytes(cipher_text);
+    let rejection_key = hash(DOMAIN_REJECT, &[&secret_key.rejection_seed, &bytes]);
+
+    // Constant-time select between key and rejection_key
+    let recomputed = S::ciphertext_bytes(&recomputed);
+    let diff = bytes
+        .iter()
+        .zip(recomputed.iter())
+        .fold((bytes.len() != recomputed.len()) as u8, |acc, (x, y)| {
+            acc | (x ^ y)
+        });
+    let mask = ((diff as u16).wrapping_neg() >> 8) as u8; // 0xff if the ciphertexts differ
+
+    let mut shared = [0u8; 32];
+    for i in 0..32 {
+        shared[i] = (key[i] & !mask) | (rejection_key[i] & mask);
+    }
+    shared
+}
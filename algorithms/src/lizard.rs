@@ -1,162 +1,346 @@
-use rand::Rng;
+use ndarray::{Array1, Array2};
+use rand::{CryptoRng, Rng, RngCore};
 use std::time::{Duration, Instant};
 
-// Parameters
-const LWE_N: usize = 536;
-const LWE_M: usize = 1024;
-const LWE_L: usize = 256;
-const LOG_Q: u32 = 11;
-const _16_LOG_Q: u32 = 5;
-const LOG_P: u32 = 9;
-const RD_ADD: u16 = 0x40;
-const RD_AND: u16 = 0xff80;
-const LOG_T: u32 = 1;
-const _16_LOG_T: u32 = 15;
-const T: u16 = 2;
-const DEC_ADD: u16 = 0x4000;
-const HR: usize = 134;
-
-// Noise distribution
-const CDF_TABLE: [u16; 9] = [78, 226, 344, 425, 473, 495, 506, 510, 511];
-const RANDBITS: u32 = 10;
+use crate::pke::{DeterministicEncryption, PublicKeyEncryption};
+use crate::sampler::{CdtSampler, Sampler};
+
+pub mod cca;
+
+// Parameter sets, selected at runtime. Coefficients live in the top bits of a u16, so mod q,
+// mod p and mod t reductions are free and rounding to p is a single add and mask.
+// CATEGORY1_N536 is the reference set with its published CDF table. The other sets of the
+// Lizard paper belong here as well, each with its own published table, once they are vendored.
+pub struct SecurityParameters {
+    pub name: &'static str,
+    pub lwe_n: usize, // n, secret dimension
+    pub lwe_m: usize, // m, number of LWE samples in the public key
+    pub lwe_l: usize, // l, plaintext symbols per ciphertext
+    pub log_q: u32,
+    pub log_p: u32,
+    pub log_t: u32,
+    pub hr: usize, // Hamming weight of the ephemeral vector r
+    pub noise: CdtSampler,
+}
+
+// Reference noise distribution of CATEGORY1_N536
+const CDF_TABLE: [u64; 9] = [78, 226, 344, 425, 473, 495, 506, 510, 511];
 const TABLE_LENGTH: usize = 9;
+// The table is indexed by 9 random bits
+const CDF_BITS: u32 = 9;
 
-// Sample from the discrete Gaussian distribution
-fn sample_d2() -> u16 {
-    let mut rng = rand::thread_rng();
-    let rnd: u16 = rng.gen::<u16>() & 0x01ff;
-    let sign: u16 = rng.gen::<u16>() & 0x01;
-    let mut sample: u16 = 0;
+// Sampler for the reference discrete Gaussian distribution
+pub fn sampler_d2() -> CdtSampler {
+    CdtSampler::from_table(&CDF_TABLE[..TABLE_LENGTH - 1], CDF_BITS)
+}
 
-    for i in 0..TABLE_LENGTH - 1 {
-        let diff = (CDF_TABLE[i] as i32 - rnd as i32) >> 15;
-        sample = sample.wrapping_add(diff as u16);
+pub fn category1_n536() -> SecurityParameters {
+    SecurityParameters {
+        name: "CATEGORY1_N536",
+        lwe_n: 536,
+        lwe_m: 1024,
+        lwe_l: 256,
+        log_q: 11,
+        log_p: 9,
+        log_t: 1,
+        hr: 134,
+        noise: sampler_d2(),
     }
-    sample = ((-(sign as i16) as u16 ^ sample).wrapping_add(sign));
-    sample
 }
 
-// Secret key
-type SecretKey = [[i16; LWE_N]; LWE_L];
+pub fn setup() -> SecurityParameters {
+    category1_n536()
+}
+
+pub fn parameter_sets() -> Vec<SecurityParameters> {
+    vec![category1_n536()]
+}
+
+// Constants of the reference implementation, derived from the parameter set
+impl SecurityParameters {
+    fn _16_log_q(&self) -> u32 {
+        16 - self.log_q
+    }
+
+    fn rd_add(&self) -> u16 {
+        1 << (15 - self.log_p)
+    }
+
+    fn rd_and(&self) -> u16 {
+        !((1u16 << (16 - self.log_p)) - 1)
+    }
+
+    fn _16_log_t(&self) -> u32 {
+        16 - self.log_t
+    }
+
+    fn dec_add(&self) -> u16 {
+        1 << (15 - self.log_t)
+    }
+}
+
+// Public key size in bytes, A and B at log_q bits per coefficient
+pub fn public_key_size(params: &SecurityParameters) -> usize {
+    params.lwe_m * (params.lwe_n + params.lwe_l) * params.log_q as usize / 8
+}
+
+// Ciphertext size in bytes, both components rounded to log_p bits
+pub fn ciphertext_size(params: &SecurityParameters) -> usize {
+    (params.lwe_n + params.lwe_l) * params.log_p as usize / 8
+}
+
+// Keys and ciphertexts are heap-backed, so Lizard runs on threads with small stacks
+
+// Secret key, l rows of n ternary coefficients
+type SecretKey = Array2<i16>;
 
 // Public key
 pub struct PublicKey {
-    pub a: [[u16; LWE_N]; LWE_M],
-    pub b: [[u16; LWE_L]; LWE_M],
+    pub a: Array2<u16>, // m x n
+    pub b: Array2<u16>, // m x l
 }
 
 // Ciphertext
+#[derive(Debug)]
 pub struct Ciphertext {
-    pub a: [u16; LWE_N],
-    pub b: [u16; LWE_L],
+    pub a: Array1<u16>,
+    pub b: Array1<u16>,
 }
 
 // Generate secret key
-pub fn gen_sk() -> SecretKey {
-    let mut sk = [[0i16; LWE_N]; LWE_L];
-    let mut rng = rand::thread_rng();
+pub fn gen_sk(params: &SecurityParameters, rng: &mut (impl RngCore + CryptoRng)) -> SecretKey {
+    let mut sk = Array2::<i16>::zeros((params.lwe_l, params.lwe_n));
 
-    for i in 0..LWE_L {
-        for j in 0..LWE_N {
-            sk[i][j] = (rng.gen::<u16>() & 0x01) as i16 + (rng.gen::<u16>() & 0x01) as i16 - 1;
+    for i in 0..params.lwe_l {
+        for j in 0..params.lwe_n {
+            sk[[i, j]] = (rng.gen::<u16>() & 0x01) as i16 + (rng.gen::<u16>() & 0x01) as i16 - 1;
         }
     }
     sk
 }
 
 // Generate public key
-pub fn gen_pk(sk: &SecretKey) -> PublicKey {
+pub fn gen_pk(
+    params: &SecurityParameters,
+    sk: &SecretKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> PublicKey {
     let mut pk = PublicKey {
-        a: [[0; LWE_N]; LWE_M],
-        b: [[0; LWE_L]; LWE_M],
+        a: Array2::zeros((params.lwe_m, params.lwe_n)),
+        b: Array2::zeros((params.lwe_m, params.lwe_l)),
     };
-    let mut rng = rand::thread_rng();
+    let shift = params._16_log_q();
 
     // Generate matrix A
-    for i in 0..LWE_M {
-        for j in 0..LWE_N {
-            pk.a[i][j] = rng.gen::<u16>() << _16_LOG_Q;
+    for i in 0..params.lwe_m {
+        for j in 0..params.lwe_n {
+            pk.a[[i, j]] = rng.gen::<u16>() << shift;
         }
     }
 
     // Generate matrix B = A * sk + E
-    for i in 0..LWE_M {
-        for j in 0..LWE_L {
+    for i in 0..params.lwe_m {
+        for j in 0..params.lwe_l {
             let mut sum = 0u16;
-            for k in 0..LWE_N {
-                sum = sum.wrapping_add(pk.a[i][k].wrapping_mul(sk[j][k] as u16));
+            for k in 0..params.lwe_n {
+                sum = sum.wrapping_add(pk.a[[i, k]].wrapping_mul(sk[[j, k]] as u16));
             }
-            pk.b[i][j] = sum.wrapping_add(sample_d2() << _16_LOG_Q);
+            pk.b[[i, j]] = sum.wrapping_add((params.noise.sample(rng) as u16) << shift);
         }
     }
     pk
 }
 
-// Encrypt plaintext
-pub fn encrypt(pk: &PublicKey, plaintext: &[u16; LWE_L]) -> Ciphertext {
+// Encrypt l symbols of Z_t: c = (A^T r, B^T r + m * q/t) rounded to p, r sparse and ternary
+pub fn encrypt(
+    params: &SecurityParameters,
+    pk: &PublicKey,
+    plaintext: &[u16],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Ciphertext {
+    if plaintext.len() != params.lwe_l {
+        panic!("Plaintext must have exactly {} symbols!", params.lwe_l);
+    }
+    if plaintext.iter().any(|&x| x >> params.log_t != 0) {
+        panic!("Plaintext symbols must be below t = {}!", 1 << params.log_t);
+    }
+
+    // Message scaled by q/t, i.e. into the top log_t bits
+    let shift = params._16_log_t();
     let mut ctx = Ciphertext {
-        a: [0; LWE_N],
-        b: plaintext.clone(),
+        a: Array1::zeros(params.lwe_n),
+        b: plaintext.iter().map(|&x| x << shift).collect(),
     };
-    let mut rng = rand::thread_rng();
 
-    // Generate sparse vector r
-    let mut r_idx = [0usize; HR];
+    // Generate sparse vector r, two (sign, index) pairs per 64 random bits
+    let hr = params.hr;
+    let mut r_idx = vec![0usize; hr];
     let neg_start = {
         let mut neg_start = 0;
-        for i in 0..HR / 2 {
+        for i in 0..hr / 2 {
             let tmp: u64 = rng.gen();
             neg_start += (tmp & 0x01) as usize;
-            r_idx[2 * i] = ((tmp >> 1) & 0x03ff) as usize % LWE_M;
-            neg_start += ((tmp >> 10) & 0x01) as usize;
-            r_idx[2 * i + 1] = ((tmp >> 12) & 0x03ff) as usize % LWE_M;
+            r_idx[2 * i] = ((tmp >> 1) & 0xffff) as usize % params.lwe_m;
+            neg_start += ((tmp >> 32) & 0x01) as usize;
+            r_idx[2 * i + 1] = ((tmp >> 33) & 0xffff) as usize % params.lwe_m;
         }
         neg_start
     };
 
-    // Compute A^T * r and B^T * r
-    for i in 0..HR {
-        let s = if i < neg_start { 1 } else { 0 };
-        let pk_a_ri = &pk.a[r_idx[i]];
-        let pk_b_ri = &pk.b[r_idx[i]];
-
-        for j in 0..LWE_N {
-            ctx.a[j] = ctx.a[j].wrapping_add((s as u16).wrapping_mul(pk_a_ri[j]));
-            ctx.a[j] = ctx.a[j]
-                .wrapping_sub((1 - s) as u16)
-                .wrapping_mul(pk_a_ri[j]);
+    // Compute A^T * r and B^T * r: add the first neg_start selected rows, subtract the others
+    for i in 0..hr {
+        // 0 to add the row, 0xffff to subtract it: (x ^ negate) - negate = -x
+        let negate = ((i >= neg_start) as u16).wrapping_neg();
+        let pk_a_ri = pk.a.row(r_idx[i]);
+        let pk_b_ri = pk.b.row(r_idx[i]);
+
+        for j in 0..params.lwe_n {
+            ctx.a[j] = ctx.a[j].wrapping_add((pk_a_ri[j] ^ negate).wrapping_sub(negate));
         }
-        for j in 0..LWE_L {
-            ctx.b[j] = ctx.b[j].wrapping_add((s as u16).wrapping_mul(pk_b_ri[j]));
-            ctx.b[j] = ctx.b[j]
-                .wrapping_sub((1 - s) as u16)
-                .wrapping_mul(pk_b_ri[j]);
+        for j in 0..params.lwe_l {
+            ctx.b[j] = ctx.b[j].wrapping_add((pk_b_ri[j] ^ negate).wrapping_sub(negate));
         }
     }
 
     // Round to modulus p
-    for i in 0..LWE_N {
-        ctx.a[i] = (ctx.a[i].wrapping_add(RD_ADD)) & RD_AND;
+    let (rd_add, rd_and) = (params.rd_add(), params.rd_and());
+    for i in 0..params.lwe_n {
+        ctx.a[i] = (ctx.a[i].wrapping_add(rd_add)) & rd_and;
     }
-    for i in 0..LWE_L {
-        ctx.b[i] = (ctx.b[i].wrapping_add(RD_ADD)) & RD_AND;
+    for i in 0..params.lwe_l {
+        ctx.b[i] = (ctx.b[i].wrapping_add(rd_add)) & rd_and;
     }
     ctx
 }
 
-// Decrypt ciphertext
-pub fn decrypt(sk: &SecretKey, ctx: &Ciphertext) -> [u16; LWE_L] {
+// Decrypt ciphertext: b - <a, s_i> = m_i * q/t + small noise, rounded to the top log_t bits
+pub fn decrypt(params: &SecurityParameters, sk: &SecretKey, ctx: &Ciphertext) -> Array1<u16> {
     let mut plaintext = ctx.b.clone();
 
-    for i in 0..LWE_L {
-        let sk_i = &sk[i]; // Access the i-th row of sk
-        for j in 0..LWE_N {
-            plaintext[i] = plaintext[i].wrapping_add(ctx.a[j].wrapping_mul(sk_i[j] as u16));
+    for i in 0..params.lwe_l {
+        let sk_i = sk.row(i); // Access the i-th row of sk
+        for j in 0..params.lwe_n {
+            plaintext[i] = plaintext[i].wrapping_sub(ctx.a[j].wrapping_mul(sk_i[j] as u16));
         }
     }
 
-    for i in 0..LWE_L {
-        plaintext[i] = (plaintext[i].wrapping_add(DEC_ADD)) >> _16_LOG_T;
+    let (dec_add, shift) = (params.dec_add(), params._16_log_t());
+    for i in 0..params.lwe_l {
+        plaintext[i] = (plaintext[i].wrapping_add(dec_add)) >> shift;
     }
     plaintext
 }
+
+// Bytes to Z_t symbols, log_t bits each, least significant first. Pads with zero symbols to l
+pub fn encode_message(params: &SecurityParameters, message: &[u8]) -> Vec<u16> {
+    let log_t = params.log_t as usize;
+    if message.len() * 8 > params.lwe_l * log_t {
+        panic!(
+            "Message must have at most {} bytes!",
+            params.lwe_l * log_t / 8
+        );
+    }
+
+    let mut symbols = vec![0u16; params.lwe_l];
+    for (bit, value) in message
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1))
+        .enumerate()
+    {
+        symbols[bit / log_t] |= (value as u16) << (bit % log_t);
+    }
+    symbols
+}
+
+// Inverse of encode_message, returns l * log_t / 8 bytes
+pub fn decode_message(params: &SecurityParameters, symbols: &[u16]) -> Vec<u8> {
+    let log_t = params.log_t as usize;
+    let mut message = vec![0u8; params.lwe_l * log_t / 8];
+    for bit in 0..message.len() * 8 {
+        let value = (symbols[bit / log_t] >> (bit % log_t)) & 1;
+        message[bit / 8] |= (value as u8) << (bit % 8);
+    }
+    message
+}
+
+// Encrypt and decrypt `trials` random symbols, return the fraction that decrypts incorrectly
+pub fn failure_rate(
+    trials: usize,
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> f64 {
+    let sk = gen_sk(params, rng);
+    let pk = gen_pk(params, &sk, rng);
+
+    let mut failures = 0;
+    let mut done = 0;
+    while done < trials {
+        let plaintext: Vec<u16> = (0..params.lwe_l)
+            .map(|_| rng.gen_range(0..1 << params.log_t))
+            .collect();
+        let length = params.lwe_l.min(trials - done);
+
+        let result = decrypt(params, &sk, &encrypt(params, &pk, &plaintext, rng));
+
+        failures += (0..length).filter(|&i| plaintext[i] != result[i]).count();
+        done += length;
+    }
+
+    failures as f64 / trials as f64
+}
+
+pub struct Lizard;
+
+impl PublicKeyEncryption for Lizard {
+    type Params = SecurityParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = SecretKey;
+    type Ciphertext = Ciphertext;
+
+    fn keygen(
+        params: &SecurityParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, SecretKey) {
+        let sk = gen_sk(params, rng);
+        let pk = gen_pk(params, &sk, rng);
+        (pk, sk)
+    }
+
+    // Plaintext holds l symbols of Z_t
+    fn encrypt(
+        params: &SecurityParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Ciphertext {
+        let message: Vec<u16> = plain_text.iter().map(|&x| x as u16).collect();
+        encrypt(params, public_key, &message, rng)
+    }
+
+    fn decrypt(
+        params: &SecurityParameters,
+        secret_key: &SecretKey,
+        cipher_text: &Ciphertext,
+    ) -> Vec<i64> {
+        decrypt(params, secret_key, cipher_text)
+            .iter()
+            .map(|&x| x as i64)
+            .collect()
+    }
+}
+
+impl DeterministicEncryption for Lizard {
+    // The transform hands over one bit per plaintext symbol, whatever t is
+    fn message_bits(params: &SecurityParameters) -> usize {
+        params.lwe_l
+    }
+
+    fn ciphertext_bytes(cipher_text: &Ciphertext) -> Vec<u8> {
+        cipher_text
+            .a
+            .iter()
+            .chain(cipher_text.b.iter())
+            .flat_map(|x| x.to_le_bytes())
+            .collect()
+    }
+}
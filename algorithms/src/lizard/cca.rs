@@ -0,0 +1,103 @@
+// Lizard.CCA: the CPA core made CCA-secure with hash functions over the plaintext and randomness.
+// A random delta is encrypted under coins G(delta), the message is masked with H(delta) and the
+// tag H'(delta || m) binds both. Decryption re-encrypts delta and checks the tag, so any change
+// to the ciphertext is rejected.
+use rand::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Shake256};
+
+use super::{
+    decode_message, decrypt as decrypt_cpa, encode_message, encrypt as encrypt_cpa, Lizard,
+    PublicKey, SecretKey, SecurityParameters,
+};
+use crate::pke::DeterministicEncryption;
+use crate::rng;
+
+#[derive(Debug)]
+pub struct Ciphertext {
+    pub c1: super::Ciphertext, // CPA encryption of delta
+    pub c2: Vec<u8>,           // m xor H(delta)
+    pub d: [u8; 32],           // H'(delta || m)
+}
+
+// G(delta): encryption coins
+fn hash_g(delta: &[u8]) -> [u8; 32] {
+    Sha3_256::new_with_prefix([0u8])
+        .chain_update(delta)
+        .finalize()
+        .into()
+}
+
+// H(delta): mask of the message length
+fn hash_h(delta: &[u8], length: usize) -> Vec<u8> {
+    let mut hasher = Shake256::default();
+    hasher.update(&[1u8]);
+    hasher.update(delta);
+    let mut mask: Vec<u8> = vec![0; length];
+    hasher.finalize_xof().read(&mut mask);
+    mask
+}
+
+// H'(delta || m): tag
+fn hash_tag(delta: &[u8], message: &[u8]) -> [u8; 32] {
+    Sha3_256::new_with_prefix([2u8])
+        .chain_update(delta)
+        .chain_update(message)
+        .finalize()
+        .into()
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b.iter()).map(|(x, y)| x ^ y).collect()
+}
+
+// Encrypt a message of any length under a key from gen_sk / gen_pk
+pub fn encrypt(
+    params: &SecurityParameters,
+    pk: &PublicKey,
+    message: &[u8],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Ciphertext {
+    let mut delta: Vec<u8> = vec![0; params.lwe_l * params.log_t as usize / 8];
+    rng.fill_bytes(&mut delta);
+
+    let c1 = encrypt_cpa(
+        params,
+        pk,
+        &encode_message(params, &delta),
+        &mut rng::chacha(hash_g(&delta)),
+    );
+
+    Ciphertext {
+        c1,
+        c2: xor(message, &hash_h(&delta, message.len())),
+        d: hash_tag(&delta, message),
+    }
+}
+
+// None if the ciphertext was not produced by encrypt under this key
+pub fn decrypt(
+    params: &SecurityParameters,
+    sk: &SecretKey,
+    pk: &PublicKey,
+    cipher_text: &Ciphertext,
+) -> Option<Vec<u8>> {
+    let delta = decode_message(params, &decrypt_cpa(params, sk, &cipher_text.c1).to_vec());
+    let message = xor(&cipher_text.c2, &hash_h(&delta, cipher_text.c2.len()));
+
+    let c1 = encrypt_cpa(
+        params,
+        pk,
+        &encode_message(params, &delta),
+        &mut rng::chacha(hash_g(&delta)),
+    );
+
+    // Both checks always run, the result only reveals that one of them failed
+    let reencrypted = Lizard::ciphertext_bytes(&c1) == Lizard::ciphertext_bytes(&cipher_text.c1);
+    let tagged = hash_tag(&delta, &message) == cipher_text.d;
+    if reencrypted & tagged {
+        Some(message)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,421 @@
+// Learning With Rounding: the LWE error is replaced by deterministic rounding from q down to p.
+// Both moduli are powers of two, so rounding is an add and a shift and nothing is sampled
+// besides the public matrix and the secrets.
+//
+// Plain LWR follows regev.rs and shares its key and ciphertext types, with b = round_p(s.A).
+// Ring and module LWR follow Saber over the ring elements of module.rs; ring LWR is rank 1.
+use ndarray::{Array1, Array2};
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::module::{inner_product, matrix_vector, sample_ring_matrix, RingMatrix, RingVector};
+use crate::pke::PublicKeyEncryption;
+use crate::regev::{Ciphertext, PrivateKey, PublicKey};
+use crate::ringlwe::RingElement;
+use crate::sampler::{BinomialSampler, Sampler};
+
+pub struct PlainParameters {
+    pub dimensions: i64, // n
+    pub rank: i64,       // m
+    pub log_q: u32,
+    pub log_p: u32,
+}
+
+pub struct ModuleParameters {
+    pub rank: usize,   // k, 1 for ring LWR
+    pub degree: usize, // n
+    pub log_q: u32,
+    pub log_p: u32,
+    pub log_t: u32, // precision kept of the message-carrying component
+    pub eta: u32,   // secrets follow the centered binomial B(eta)
+}
+
+// Same dimension and number of samples as regev::setup, q = 2^12 and p = 2^8
+pub fn plain() -> PlainParameters {
+    let dimensions = 128;
+    let log_q = 12;
+    PlainParameters {
+        dimensions,
+        rank: (1.1 * dimensions as f64 * log_q as f64).ceil() as i64,
+        log_q,
+        log_p: 8,
+    }
+}
+
+// Single ring element of degree 1024
+pub fn ring() -> ModuleParameters {
+    ModuleParameters {
+        rank: 1,
+        degree: 1024,
+        log_q: 13,
+        log_p: 10,
+        log_t: 4,
+        eta: 4,
+    }
+}
+
+// Saber moduli over degree 256: q = 2^13, p = 2^10, t = 2^4. Rank 3 is Saber itself
+pub fn module(rank: usize) -> ModuleParameters {
+    ModuleParameters {
+        rank,
+        degree: 256,
+        log_q: 13,
+        log_p: 10,
+        log_t: 4,
+        eta: 4,
+    }
+}
+
+// round_p(x) = floor((x + q/2p) / (q/p)) mod p, for x in [0, q)
+fn round(x: i64, log_q: u32, log_p: u32) -> i64 {
+    ((x + (1 << (log_q - log_p - 1))) >> (log_q - log_p)) & ((1 << log_p) - 1)
+}
+
+// Centered representative of x mod 2^bits
+fn center(x: i64, bits: u32) -> i64 {
+    let modulo = 1 << bits;
+    let x = x & (modulo - 1);
+    if x > modulo / 2 {
+        x - modulo
+    } else {
+        x
+    }
+}
+
+fn uniform_array2(
+    rows: i64,
+    cols: i64,
+    modulo: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Array2<i64> {
+    let mut matrix = Array2::<i64>::zeros((rows as usize, cols as usize));
+    for elem in matrix.iter_mut() {
+        *elem = rng.gen_range(0..modulo);
+    }
+    matrix
+}
+
+// Plain LWR: A uniform n x m mod q, s uniform mod q, b = round_p(s.A) mod p
+pub fn plain_keygen(
+    params: &PlainParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (PublicKey, PrivateKey) {
+    let q = 1 << params.log_q;
+    let a = uniform_array2(params.dimensions, params.rank, q, rng);
+    let s = Array1::from_vec(
+        (0..params.dimensions)
+            .map(|_| rng.gen_range(0..q))
+            .collect(),
+    );
+
+    let b = s
+        .dot(&a)
+        .mapv(|x| round(x & (q - 1), params.log_q, params.log_p));
+
+    (
+        PublicKey {
+            matrix: a,
+            public_vector: b,
+        },
+        PrivateKey { secret_vector: s },
+    )
+}
+
+// Preamble A.x mod q, scalars b.x + m * p/2 mod p, x binary as in regev::encrypt
+pub fn plain_encrypt(
+    plain_text: &Array1<i64>,
+    public_key: &PublicKey,
+    params: &PlainParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Ciphertext {
+    let (q, p) = (1 << params.log_q, 1 << params.log_p);
+    let x = uniform_array2(params.rank, plain_text.len() as i64, 2, rng);
+
+    Ciphertext {
+        preamble: public_key.matrix.dot(&x).mapv(|v| v & (q - 1)),
+        scalars: (public_key.public_vector.dot(&x) + plain_text * (p / 2)).mapv(|v| v & (p - 1)),
+    }
+}
+
+// Lift the scalars back to q: (q/p) c - s.(A x) = m * q/2 + e.x mod q, with e = (q/p) b - s.A.
+// Each e_j is an integer in (-q/2p, q/2p], +1/2 on average since rounding goes half up, and x
+// selects about m/2 samples, so the expected drift of m/4 is removed before decoding.
+fn plain_noisy(
+    cipher_text: &Ciphertext,
+    private_key: &PrivateKey,
+    params: &PlainParameters,
+) -> Array1<i64> {
+    let q = 1 << params.log_q;
+    (cipher_text
+        .scalars
+        .mapv(|c| c << (params.log_q - params.log_p))
+        - private_key.secret_vector.dot(&cipher_text.preamble))
+    .mapv(|x| (x - params.rank / 4) & (q - 1))
+}
+
+pub fn plain_decrypt(
+    cipher_text: &Ciphertext,
+    private_key: &PrivateKey,
+    params: &PlainParameters,
+) -> Array1<i64> {
+    let q = 1 << params.log_q;
+    plain_noisy(cipher_text, private_key, params).mapv(|x| {
+        if center(x, params.log_q).abs() > q / 4 {
+            1
+        } else {
+            0
+        }
+    })
+}
+
+// Decryption noise e.x of `trials` random bits, centered mod q. Decryption fails beyond q/4
+pub fn decryption_noise(
+    trials: usize,
+    params: &PlainParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<i64> {
+    let (public_key, private_key) = plain_keygen(params, rng);
+    let chunk = params.dimensions as usize;
+    let q = 1 << params.log_q;
+
+    let mut noise: Vec<i64> = Vec::with_capacity(trials);
+    while noise.len() < trials {
+        let length = chunk.min(trials - noise.len());
+        let plain_text = uniform_array2(1, length as i64, 2, rng).row(0).to_owned();
+
+        let cipher_text = plain_encrypt(&plain_text, &public_key, params, rng);
+        let noisy = plain_noisy(&cipher_text, &private_key, params);
+
+        noise.extend(
+            noisy
+                .iter()
+                .zip(plain_text.iter())
+                .map(|(x, m)| center(x - m * q / 2, params.log_q)),
+        );
+    }
+    noise
+}
+
+// Module LWR keys, A over R_q and b = round_p(A^T s) over R_p
+pub struct ModulePublicKey {
+    pub matrix: RingMatrix,
+    pub public_vector: RingVector,
+}
+
+pub struct ModulePrivateKey {
+    pub secret_vector: RingVector,
+}
+
+#[derive(Debug)]
+pub struct ModuleCiphertext {
+    pub preamble: RingVector, // round_p(A s'), over R_p
+    pub scalars: RingElement, // message component, over R_t
+}
+
+fn sample_secret(
+    params: &ModuleParameters,
+    modulo: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> RingVector {
+    let sampler = BinomialSampler::new(params.eta);
+    (0..params.rank)
+        .map(|_| RingElement::from_coefficients(&sampler.sample_vec(rng, params.degree), modulo))
+        .collect()
+}
+
+// Same small coefficients, reduced mod another modulus
+fn change_modulo(v: &[RingElement], modulo: i64) -> RingVector {
+    v.iter()
+        .map(|x| RingElement::from_coefficients(&x.centered(), modulo))
+        .collect()
+}
+
+// Round every coefficient from R_q to R_p. Adding the constant h1 = q/2p first makes the
+// truncation a rounding, as in Saber
+fn round_vector(v: &[RingElement], log_q: u32, log_p: u32) -> RingVector {
+    v.iter()
+        .map(|x| RingElement {
+            coefficients: x
+                .coefficients
+                .iter()
+                .map(|&c| round(c, log_q, log_p))
+                .collect(),
+            modulo: 1 << log_p,
+        })
+        .collect()
+}
+
+pub fn module_keygen(
+    params: &ModuleParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (ModulePublicKey, ModulePrivateKey) {
+    let q = 1 << params.log_q;
+    let a = sample_ring_matrix(params.rank, params.degree, q, rng);
+    let s = sample_secret(params, q, rng);
+
+    let b = round_vector(&matrix_vector(&a, &s, true), params.log_q, params.log_p);
+    (
+        ModulePublicKey {
+            matrix: a,
+            public_vector: b,
+        },
+        ModulePrivateKey { secret_vector: s },
+    )
+}
+
+// b' = round_p(A s'), v' = b^T s' mod p, c = round_t(v' + h1 - m * p/2)
+pub fn module_encrypt(
+    params: &ModuleParameters,
+    public_key: &ModulePublicKey,
+    m: &[i64],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> ModuleCiphertext {
+    if m.len() > params.degree {
+        panic!("Message must have at most {} bits!", params.degree);
+    }
+    let (q, p) = (1 << params.log_q, 1 << params.log_p);
+
+    let s1 = sample_secret(params, q, rng);
+    let preamble = round_vector(
+        &matrix_vector(&public_key.matrix, &s1, false),
+        params.log_q,
+        params.log_p,
+    );
+
+    let v = inner_product(&public_key.public_vector, &change_modulo(&s1, p));
+    let h1 = 1 << (params.log_q - params.log_p - 1);
+
+    let mut message: Vec<i64> = vec![0; params.degree];
+    message[..m.len()].copy_from_slice(m);
+    let scalars: Vec<i64> = v
+        .coefficients
+        .iter()
+        .zip(message.iter())
+        .map(|(&x, &bit)| ((x + h1 - bit * (p / 2)) & (p - 1)) >> (params.log_p - params.log_t))
+        .collect();
+
+    ModuleCiphertext {
+        preamble,
+        scalars: RingElement::from_coefficients(&scalars, 1 << params.log_t),
+    }
+}
+
+// v = b'^T s mod p, m = round_2(v - (p/t) c + h2)
+pub fn module_decrypt(
+    params: &ModuleParameters,
+    private_key: &ModulePrivateKey,
+    cipher_text: &ModuleCiphertext,
+) -> Vec<i64> {
+    let p = 1 << params.log_p;
+    let v = inner_product(
+        &cipher_text.preamble,
+        &change_modulo(&private_key.secret_vector, p),
+    );
+
+    let h2 = (1 << (params.log_p - 2)) - (1 << (params.log_p - params.log_t - 1))
+        + (1 << (params.log_q - params.log_p - 1));
+    v.coefficients
+        .iter()
+        .zip(cipher_text.scalars.coefficients.iter())
+        .map(|(&x, &c)| {
+            ((x - (c << (params.log_p - params.log_t)) + h2) & (p - 1)) >> (params.log_p - 1)
+        })
+        .collect()
+}
+
+// Encrypt and decrypt `trials` random bits, return the fraction that decrypts incorrectly
+pub fn module_failure_rate(
+    trials: usize,
+    params: &ModuleParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> f64 {
+    let (public_key, private_key) = module_keygen(params, rng);
+
+    let mut failures = 0;
+    let mut done = 0;
+    while done < trials {
+        let length = params.degree.min(trials - done);
+        let m: Vec<i64> = (0..length).map(|_| rng.gen_range(0..2)).collect();
+
+        let result = module_decrypt(
+            params,
+            &private_key,
+            &module_encrypt(params, &public_key, &m, rng),
+        );
+
+        failures += m.iter().zip(result.iter()).filter(|(x, y)| x != y).count();
+        done += length;
+    }
+
+    failures as f64 / trials as f64
+}
+
+pub struct PlainLwr;
+
+impl PublicKeyEncryption for PlainLwr {
+    type Params = PlainParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = PrivateKey;
+    type Ciphertext = Ciphertext;
+
+    fn keygen(
+        params: &PlainParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, PrivateKey) {
+        plain_keygen(params, rng)
+    }
+
+    fn encrypt(
+        params: &PlainParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Ciphertext {
+        plain_encrypt(
+            &Array1::from_vec(plain_text.to_vec()),
+            public_key,
+            params,
+            rng,
+        )
+    }
+
+    fn decrypt(
+        params: &PlainParameters,
+        secret_key: &PrivateKey,
+        cipher_text: &Ciphertext,
+    ) -> Vec<i64> {
+        plain_decrypt(cipher_text, secret_key, params).to_vec()
+    }
+}
+
+pub struct ModuleLwr;
+
+impl PublicKeyEncryption for ModuleLwr {
+    type Params = ModuleParameters;
+    type PublicKey = ModulePublicKey;
+    type SecretKey = ModulePrivateKey;
+    type Ciphertext = ModuleCiphertext;
+
+    fn keygen(
+        params: &ModuleParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (ModulePublicKey, ModulePrivateKey) {
+        module_keygen(params, rng)
+    }
+
+    fn encrypt(
+        params: &ModuleParameters,
+        public_key: &ModulePublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> ModuleCiphertext {
+        module_encrypt(params, public_key, plain_text, rng)
+    }
+
+    fn decrypt(
+        params: &ModuleParameters,
+        secret_key: &ModulePrivateKey,
+        cipher_text: &ModuleCiphertext,
+    ) -> Vec<i64> {
+        module_decrypt(params, secret_key, cipher_text)
+    }
+}
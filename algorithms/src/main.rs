@@ -2,18 +2,32 @@
 // Comment this to allow warnings
 #![allow(warnings)]
 mod coppersmith;
+mod fo;
 // mod lizard;
 mod lizard;
+mod lwr;
+mod mlkem;
 mod module;
 mod multiparty;
+mod ntru;
+mod ntt;
+mod pke;
 mod regev;
 mod ringlwe;
+mod rlizard;
+mod rng;
+mod sampler;
 
 use nalgebra::{DMatrix, DVector};
 use ndarray::{Array1, Array2};
+use pke::{bytes_to_bits, DeterministicEncryption, PublicKeyEncryption};
 use rand::Rng;
+use ringlwe::RingElement;
+use rug::ops::RemRounding;
+use rug::rand::RandState;
 use rug::Integer;
 use rustfft::Length;
+use sampler::{BinomialSampler, CdtSampler, KnuthYaoSampler, Sampler};
 use std::time::{Duration, Instant};
 
 // Used to generate random bit stream for testing
@@ -28,154 +42,839 @@ fn __gen_random_array1__(size: i64, modulo: i64) -> Array1<i64> {
     return matrix;
 }
 
-fn regev(message_length: i64) {
-    let preamble: Array2<i64>;
-    let scalars: Array1<i64>;
-
-    let pub_key: regev::PublicKey;
-    let priv_key: regev::PrivateKey;
-
-    let params: regev::SecurityParameters = regev::setup();
-
-    println!("Message Length: {}", message_length);
-
-    // Measure times from here
-
-    let plain_text_bits = __gen_random_array1__(message_length, 2);
-
+// Benchmark any scheme through the common PublicKeyEncryption interface
+fn benchmark<S: PublicKeyEncryption>(params: &S::Params, plain_text: &[i64]) {
     let mut start: Instant = Instant::now();
     let mut duration: Duration;
 
-    (pub_key, priv_key) = regev::key_gen(&params);
+    let mut rng = rand::thread_rng();
+
+    let (pub_key, priv_key) = S::keygen(params, &mut rng);
 
     duration = start.elapsed();
     println!("Time KeyGen: {:?}", duration);
 
     start = Instant::now();
 
-    (preamble, scalars) = regev::encrypt(&plain_text_bits, &pub_key, &params);
+    let cipher_text = S::encrypt(params, &pub_key, plain_text, &mut rng);
 
     duration = start.elapsed();
     println!("Time Enc: {:?}", duration);
 
-    // println!("Encryption: {:?} \n {:?}", preamble, scalars);
-
     start = Instant::now();
 
-    let result = regev::decrypt(preamble, scalars, &priv_key, &params);
+    let result = S::decrypt(params, &priv_key, &cipher_text);
 
     duration = start.elapsed();
     println!("Time Dec: {:?}", duration);
 
-    println!("Result: {}", plain_text_bits == result);
+    println!("Success: {}", result == plain_text);
 }
 
-fn lizard() {
-    println!("\n======== LIZARD ========");
+fn regev(message_length: i64) {
+    let params: regev::SecurityParameters = regev::setup();
 
-    let mut start: Instant = Instant::now();
-    let mut duration: Duration;
+    println!("Message Length: {}", message_length);
 
-    let sk = lizard::gen_sk();
-    let pk = lizard::gen_pk(&sk);
+    let plain_text: Vec<i64> = __gen_random_array1__(message_length, 2).to_vec();
 
-    duration = start.elapsed();
-    println!("Time KeyGen: {:?}", duration);
+    benchmark::<regev::Regev>(&params, &plain_text);
+}
 
-    // println!("SECRET KEY: {:?}", sk);
-    // println!("PUBLIC KEY: A {:?} || B: {:?}", pk.a, pk.b);
+// Statistical correctness check: decryption failures over many independent encryptions
+fn regev_failure_rate(trials: usize) {
+    let params: regev::SecurityParameters = regev::setup();
 
-    let plaintext = [1u16; 256];
+    let rate = regev::failure_rate(trials, &params, &mut rand::thread_rng());
 
-    // println!("PLAINTEXT: {:?}", plaintext);
+    println!("Decryption failure rate ({} bits): {}", trials, rate);
+    println!("Success: {}", rate == 0.0);
+}
 
-    start = Instant::now();
+// Known-answer check: the same seed must reproduce the same ciphertext byte for byte.
+// The fingerprints hash the canonical ciphertext bytes, are stable across runs and machines
+// and pinned in known_answers, so any change to a scheme's output shows up here.
+// Update them only with a deliberate change.
+fn known_answer<S: PublicKeyEncryption>(
+    name: &str,
+    params: &S::Params,
+    plain_text: &[i64],
+    ciphertext_bytes: fn(&S::Ciphertext) -> Vec<u8>,
+    expected: (&str, &str),
+) {
+    let chacha = |seed: [u8; 32]| {
+        let mut rng = rng::chacha(seed);
+        let (pub_key, _) = S::keygen(params, &mut rng);
+        let cipher_text = S::encrypt(params, &pub_key, plain_text, &mut rng);
+        rng::fingerprint(&ciphertext_bytes(&cipher_text))
+    };
+    let shake = |seed: &[u8]| {
+        let mut rng = rng::ShakeRng::new(seed);
+        let (pub_key, _) = S::keygen(params, &mut rng);
+        let cipher_text = S::encrypt(params, &pub_key, plain_text, &mut rng);
+        rng::fingerprint(&ciphertext_bytes(&cipher_text))
+    };
+
+    let chacha_kat = chacha([42; 32]);
+    let shake_kat = shake(b"crypto-lattice");
+
+    println!("{} (ChaCha20): {}", name, chacha_kat);
+    println!("{} (SHAKE-256): {}", name, shake_kat);
+    println!(
+        "Success: {}",
+        (chacha_kat.as_str(), shake_kat.as_str()) == expected
+    );
+}
 
-    let ctx = lizard::encrypt(&pk, &plaintext);
+// Expected (ChaCha20, SHAKE-256) fingerprints of known_answer
+const REGEV_KAT: (&str, &str) = (
+    "87add6bdc1c3d05ea19a03f777218323",
+    "cc84b91c582fcc935856f11e5f1c6513",
+);
+const RING_LWE_KAT: (&str, &str) = (
+    "6c7a22878c984eab77574a650c1fb471",
+    "665465d86e7258132d8cd07c4323bcf0",
+);
+const NTRU_KAT: (&str, &str) = (
+    "19952578356d10040eb90522d3b787f3",
+    "e959bdbc14c19b25abb709f376825192",
+);
+const MODULE_LWE_KAT: (&str, &str) = (
+    "1be069f9a4d7a2341c041b05ec0d2d42",
+    "c3a24d3c4c4ba82709adfd7ecc35d7dc",
+);
+const LIZARD_KAT: (&str, &str) = (
+    "d8ccea52c2a9f1765a40e5502020c1da",
+    "1d61f2e7163ac1780ae787a4cce95999",
+);
+const RLIZARD_KAT: (&str, &str) = (
+    "c5aa3f870aaa36061f13dc77497d5004",
+    "9cfff1b9c4678f69c74a9485adf9daa2",
+);
+
+fn known_answers() {
+    let bits: Vec<i64> = (0..512).map(|i| (i * 7 % 3) % 2).collect();
+    let ternary: Vec<i64> = (0..167).map(|i| i % 3 - 1).collect();
+
+    known_answer::<regev::Regev>(
+        "Regev",
+        &regev::setup(),
+        &bits[..128],
+        regev::Regev::ciphertext_bytes,
+        REGEV_KAT,
+    );
+    known_answer::<ringlwe::RingLwe>(
+        "Ring-LWE",
+        &ringlwe::setup(),
+        &bits,
+        ringlwe::RingLwe::ciphertext_bytes,
+        RING_LWE_KAT,
+    );
+    known_answer::<ntru::Ntru>(
+        "NTRU",
+        &ntru::setup(),
+        &ternary,
+        |cipher_text| pke::coefficients_to_bytes(cipher_text),
+        NTRU_KAT,
+    );
+    known_answer::<module::ModuleLwe>(
+        "Module-LWE",
+        &module::SecurityParameters {
+            rank: 2,
+            degree: 256,
+            modulo: 3329,
+        },
+        &bits[..256],
+        module::ModuleLwe::ciphertext_bytes,
+        MODULE_LWE_KAT,
+    );
+    known_answer::<lizard::Lizard>(
+        "Lizard",
+        &lizard::setup(),
+        &bits[..256],
+        lizard::Lizard::ciphertext_bytes,
+        LIZARD_KAT,
+    );
+    known_answer::<rlizard::RLizard>(
+        "RLizard",
+        &rlizard::setup(),
+        &bits,
+        rlizard::RLizard::ciphertext_bytes,
+        RLIZARD_KAT,
+    );
+}
 
-    duration = start.elapsed();
-    println!("Time Enc: {:?}", duration);
+// Standard deviation and largest magnitude of decryption noise, relative to the q/4 bound
+fn noise_stats(name: &str, noise: &[i64], modulo: i64) {
+    let variance = noise.iter().map(|&x| (x * x) as f64).sum::<f64>() / noise.len() as f64;
+    let max = noise.iter().map(|x| x.abs()).max().unwrap_or(0);
+    println!(
+        "{}: q = {}, std dev = {:.1} ({:.4} q/4), max = {} ({:.4} q/4)",
+        name,
+        modulo,
+        variance.sqrt(),
+        variance.sqrt() / (modulo / 4) as f64,
+        max,
+        max as f64 / (modulo / 4) as f64
+    );
+}
 
-    // println!("CIPHERTEXT: Preamble {:?} || Scalars {:?}", ctx.a, ctx.b);
+// LWR round trips, and deterministic rounding noise next to Regev's Gaussian noise
+fn lwr(trials: usize) {
+    let mut rng = rand::thread_rng();
 
-    start = Instant::now();
+    println!("\n======== PLAIN LWR ========");
+    let params = lwr::plain();
+    let plain_text: Vec<i64> = __gen_random_array1__(params.dimensions, 2).to_vec();
+    benchmark::<lwr::PlainLwr>(&params, &plain_text);
+
+    let regev_params = regev::setup();
+    noise_stats(
+        "Regev noise",
+        &regev::decryption_noise(trials, &regev_params, &mut rng),
+        regev_params.modulo,
+    );
+    noise_stats(
+        "LWR noise",
+        &lwr::decryption_noise(trials, &params, &mut rng),
+        1 << params.log_q,
+    );
+
+    for (name, params) in [
+        ("RING LWR", lwr::ring()),
+        ("MODULE LWR (rank 2)", lwr::module(2)),
+        ("MODULE LWR (rank 3)", lwr::module(3)),
+    ] {
+        println!("\n======== {} ========", name);
+        let plain_text: Vec<i64> = __gen_random_array1__(params.degree as i64, 2).to_vec();
+        benchmark::<lwr::ModuleLwr>(&params, &plain_text);
+
+        let rate = lwr::module_failure_rate(trials, &params, &mut rng);
+        println!("Decryption failure rate ({} bits): {}", trials, rate);
+        println!("Success: {}", rate == 0.0);
+    }
+}
 
-    let decrypted = lizard::decrypt(&sk, &ctx);
+// Stream a byte message through Regev chunk by chunk
+fn regev_stream(message_length: usize) {
+    let params: regev::SecurityParameters = regev::setup();
+    let mut rng = rand::thread_rng();
+    let (pub_key, priv_key) = regev::key_gen(&params, &mut rng);
 
-    duration = start.elapsed();
-    println!("Time Dec: {:?}", duration);
+    let message: Vec<u8> = __gen_random_array1__(message_length as i64, 256)
+        .iter()
+        .map(|&x| x as u8)
+        .collect();
+
+    println!("Message Length: {} bytes", message_length);
+
+    let start: Instant = Instant::now();
+
+    let result = regev::decrypt_chunks(
+        regev::encrypt_chunks(&message, &pub_key, &params, &mut rng),
+        &priv_key,
+        &params,
+    );
 
-    // println!("DECRYPTED: {:?}", decrypted);
+    let duration: Duration = start.elapsed();
+    println!("Time Enc + Dec: {:?}", duration);
+
+    println!("Success: {}", result == Some(message));
 }
 
-fn ringlwe(message_length: i64) {
-    let pub_key: ringlwe::PublicKey;
-    let priv_key: ringlwe::PrivateKey;
+fn lizard() {
+    for params in lizard::parameter_sets() {
+        println!("\n======== LIZARD ({}) ========", params.name);
+        println!(
+            "n: {}, m: {}, l: {}, q: 2^{}, p: 2^{}, h_r: {}",
+            params.lwe_n, params.lwe_m, params.lwe_l, params.log_q, params.log_p, params.hr
+        );
+        println!(
+            "Public key: {} bytes, Ciphertext: {} bytes",
+            lizard::public_key_size(&params),
+            lizard::ciphertext_size(&params)
+        );
+
+        let plain_text: Vec<i64> = __gen_random_array1__(params.lwe_l as i64, 2).to_vec();
+
+        benchmark::<lizard::Lizard>(&params, &plain_text);
+
+        let trials = 10000;
+        let rate = lizard::failure_rate(trials, &params, &mut rand::thread_rng());
+        println!("Decryption failure rate ({} symbols): {}", trials, rate);
+        println!("Success: {}", rate == 0.0);
+    }
+
+    // Byte messages as t-ary symbols, with t = 2 and t = 4
+    lizard_message(&lizard::setup());
+    lizard_message(&lizard::SecurityParameters {
+        name: "CATEGORY1_N536, t = 4",
+        log_t: 2,
+        ..lizard::setup()
+    });
+
+    // Keys live on the heap, so a worker thread with a small stack is enough
+    let worker = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(|| {
+            let params = lizard::setup();
+            let mut rng = rand::thread_rng();
+            let (pub_key, _) = lizard::Lizard::keygen(&params, &mut rng);
+            lizard::Lizard::encrypt(&params, &pub_key, &vec![1; params.lwe_l], &mut rng);
+        })
+        .unwrap();
+    println!("Worker thread (256 KiB stack): {}", worker.join().is_ok());
+
+    println!("\n======== LIZARD.CCA ========");
+    lizard_cca();
+}
+
+fn lizard_message(params: &lizard::SecurityParameters) {
+    let mut rng = rand::thread_rng();
+    let sk = lizard::gen_sk(params, &mut rng);
+    let pk = lizard::gen_pk(params, &sk, &mut rng);
+
+    let message: Vec<u8> = (0..params.lwe_l * params.log_t as usize / 8)
+        .map(|_| rng.gen())
+        .collect();
+    let cipher_text = lizard::encrypt(
+        params,
+        &pk,
+        &lizard::encode_message(params, &message),
+        &mut rng,
+    );
+    let result =
+        lizard::decode_message(params, &lizard::decrypt(params, &sk, &cipher_text).to_vec());
+
+    println!("Lizard message ({}): {} bytes", params.name, message.len());
+    println!("Success: {}", result == message);
+}
+
+// RLizard next to Lizard: smaller keys, ring products instead of matrix products
+fn rlizard() {
+    for params in rlizard::parameter_sets() {
+        println!("\n======== RLIZARD ({}) ========", params.name);
+        println!(
+            "n: {}, q: 2^{}, p: 2^{}, h_s: {}, h_r: {}",
+            params.degree, params.log_q, params.log_p, params.hs, params.hr
+        );
+        println!(
+            "Public key: {} bytes, Ciphertext: {} bytes",
+            rlizard::public_key_size(&params),
+            rlizard::ciphertext_size(&params)
+        );
+
+        let plain_text: Vec<i64> = __gen_random_array1__(params.degree as i64, 2).to_vec();
+
+        benchmark::<rlizard::RLizard>(&params, &plain_text);
+
+        let trials = 10000;
+        let rate = rlizard::failure_rate(trials, &params, &mut rand::thread_rng());
+        println!("Decryption failure rate ({} symbols): {}", trials, rate);
+        println!("Success: {}", rate == 0.0);
+    }
+}
+
+// Lizard.CCA round trip, then flip one bit in each part of the ciphertext and expect rejection
+fn lizard_cca() {
+    let params = lizard::setup();
+    let mut rng = rand::thread_rng();
+    let sk = lizard::gen_sk(&params, &mut rng);
+    let pk = lizard::gen_pk(&params, &sk, &mut rng);
+
+    let message = b"Lizard.CCA rejects tampered ciphertexts".to_vec();
+
+    let start: Instant = Instant::now();
+    let mut cipher_text = lizard::cca::encrypt(&params, &pk, &message, &mut rng);
+    println!("Time Enc (CCA): {:?}", start.elapsed());
 
-    let preamble: Vec<i64>;
-    let scalars: Vec<i64>;
+    let start: Instant = Instant::now();
+    let result = lizard::cca::decrypt(&params, &sk, &pk, &cipher_text);
+    println!("Time Dec (CCA): {:?}", start.elapsed());
+    println!("Success: {}", result == Some(message));
 
+    let mut rejected = 0;
+    for bit in 0..8 {
+        cipher_text.c1.a[bit] ^= 1 << bit;
+        rejected += lizard::cca::decrypt(&params, &sk, &pk, &cipher_text).is_none() as usize;
+        cipher_text.c1.a[bit] ^= 1 << bit;
+
+        cipher_text.c1.b[bit] ^= 1 << (8 + bit);
+        rejected += lizard::cca::decrypt(&params, &sk, &pk, &cipher_text).is_none() as usize;
+        cipher_text.c1.b[bit] ^= 1 << (8 + bit);
+
+        cipher_text.c2[bit] ^= 1 << bit;
+        rejected += lizard::cca::decrypt(&params, &sk, &pk, &cipher_text).is_none() as usize;
+        cipher_text.c2[bit] ^= 1 << bit;
+
+        cipher_text.d[bit] ^= 1 << bit;
+        rejected += lizard::cca::decrypt(&params, &sk, &pk, &cipher_text).is_none() as usize;
+        cipher_text.d[bit] ^= 1 << bit;
+    }
+    println!("Tampered ciphertexts rejected: {}/32", rejected);
+    println!("Success: {}", rejected == 32);
+}
+
+fn ringlwe(message_length: i64) {
     let raw = __gen_random_array1__(message_length, 2);
     let plain_text: Vec<i64> = raw.to_vec();
 
-    let mut start: Instant = Instant::now();
-    let mut duration: Duration;
+    let start: Instant = Instant::now();
 
     let params: ringlwe::SecurityParameters = ringlwe::setup();
 
-    duration = start.elapsed();
+    let duration: Duration = start.elapsed();
     println!("Time Setup: {:?}", duration);
 
-    start = Instant::now();
+    benchmark::<ringlwe::RingLwe>(&params, &plain_text);
+}
 
-    (pub_key, priv_key) = ringlwe::key_gen(&params);
+// Encrypt an arbitrary-length message block by block
+fn ringlwe_message(message: &[u8]) {
+    let params: ringlwe::SecurityParameters = ringlwe::setup();
+    let mut rng = rand::thread_rng();
+    let (pub_key, priv_key) = ringlwe::key_gen(&params, &mut rng);
 
-    duration = start.elapsed();
-    println!("Time KeyGen: {:?}", duration);
+    println!("Message Length: {} bytes", message.len());
 
-    start = Instant::now();
+    let mut start: Instant = Instant::now();
+    let mut duration: Duration;
 
-    (preamble, scalars) = ringlwe::encrypt(&plain_text, &params, &pub_key);
+    let cipher_text = ringlwe::encrypt_message(message, &params, &pub_key, &mut rng);
 
     duration = start.elapsed();
-    println!("Time Enc: {:?}", duration);
+    println!("Time Enc ({} blocks): {:?}", cipher_text.len(), duration);
 
     start = Instant::now();
 
-    let result = ringlwe::decrypt(&preamble, &scalars, &params, &priv_key);
+    let result = ringlwe::decrypt_message(&cipher_text, &params, &priv_key);
 
     duration = start.elapsed();
     println!("Time Dec: {:?}", duration);
 
-    println!("Success: {}", result == plain_text);
+    println!("Success: {}", result.as_deref() == Some(message));
 }
 
-fn modulwe(size: usize) {
-    let k: usize = size;
-    let q: i64 = 12289;
+// Check ring arithmetic against the schoolbook reference on random elements
+fn ring_arithmetic(trials: usize) {
+    let params: ringlwe::SecurityParameters = ringlwe::setup();
+    let q = params.modulo;
+    let degree = params.dimension as usize;
+
+    let random =
+        || RingElement::from_coefficients(&__gen_random_array1__(params.dimension, q).to_vec(), q);
+
+    let mut success = true;
+    for _ in 0..trials {
+        let a = random();
+        let b = random();
+        let c = random();
+
+        // NTT product matches the schoolbook product
+        success &= a.mul_ntt(&b, &params.ntt) == &a * &b;
 
-    let start = Instant::now();
-    let (a, s0, _, p0) = module::keygen(k, q);
-    let keygen_time = start.elapsed();
+        // Distributivity and commutativity
+        success &= &a * &(&b + &c) == (&a * &b) + &(&a * &c);
+        success &= &a * &b == &b * &a;
 
-    let m = DVector::from_element(k, 1);
+        // Negation and subtraction
+        success &= &a + &(-&a) == RingElement::zero(degree, q);
+        success &= &a - &b == &a + &(-&b);
+
+        // Scalar multiplication is multiplication by a constant polynomial
+        let mut constant: Vec<i64> = vec![0; degree];
+        constant[0] = 3;
+        success &= &a * 3 == &a * &RingElement::from_coefficients(&constant, q);
+
+        // Centered and canonical representatives agree
+        success &= RingElement::from_coefficients(&a.centered(), q) == a;
+        success &= a.centered().iter().all(|x| 2 * x.abs() <= q);
+    }
+
+    // Short products keep their low coefficients, X^n wraps around to -1
+    let mut short: Vec<i64> = vec![0; degree + 1];
+    short[0] = 5;
+    short[degree] = 2;
+    let mut expected: Vec<i64> = vec![0; degree];
+    expected[0] = 3;
+    success &= ringlwe::reduce(&short, degree, q) == RingElement::from_coefficients(&expected, q);
+
+    // 3329 stops the NTT at blocks of 4, 12289 = 1 mod 2n gives a complete transform
+    let full = ntt::precompute(degree, 12289);
+    for _ in 0..trials {
+        let a = RingElement::from_coefficients(
+            &__gen_random_array1__(params.dimension, 12289).to_vec(),
+            12289,
+        );
+        let b = RingElement::from_coefficients(
+            &__gen_random_array1__(params.dimension, 12289).to_vec(),
+            12289,
+        );
+        success &= a.mul_ntt(&b, &full) == &a * &b;
+    }
+
+    println!("Success: {}", success);
+}
+
+// Chi-square goodness of fit of a sampler against its target distribution
+fn check_sampler<S: Sampler>(name: &str, sampler: &S, samples: usize) {
+    let mut rng = rand::thread_rng();
+    let drawn = sampler.sample_vec(&mut rng, samples);
+
+    let (statistic, degrees_of_freedom) = sampler::chi_square(sampler, &drawn);
+    let critical = sampler::chi_square_critical(degrees_of_freedom);
+
+    println!(
+        "{}: chi-square {:.2}, df {}, critical {:.2} -> Success: {}",
+        name,
+        statistic,
+        degrees_of_freedom,
+        critical,
+        statistic < critical
+    );
+}
+
+fn samplers(samples: usize) {
+    check_sampler("CDT (sigma 3.2)", &CdtSampler::new(3.2), samples);
+    check_sampler("Knuth-Yao (sigma 3.2)", &KnuthYaoSampler::new(3.2), samples);
+    check_sampler("Binomial (eta 2)", &BinomialSampler::new(2), samples);
+    check_sampler("Lizard CDT", &lizard::sampler_d2(), samples);
+    check_sampler(
+        "CDT (sigma 2.55, 9 bits)",
+        &CdtSampler::with_precision(2.55, 9),
+        samples,
+    );
+}
+
+fn ntru() {
+    let params: ntru::SecurityParameters = ntru::setup();
+
+    // Ternary message: coefficients in {-1, 0, 1}
+    let raw = __gen_random_array1__(params.dimension, 3);
+    let plain_text: Vec<i64> = raw.iter().map(|x| x - 1).collect();
+
+    benchmark::<ntru::Ntru>(&params, &plain_text);
+}
 
-    let start = Instant::now();
-    let (p1, c) = module::encrypt(&a, &p0, &m, q);
-    let enc_time = start.elapsed();
+fn modulwe(rank: usize, degree: usize) {
+    let params = module::SecurityParameters {
+        rank,
+        degree,
+        modulo: 3329,
+    };
 
-    let start = Instant::now();
-    let _ = module::decrypt(&p1, &c, &s0, q);
-    let dec_time = start.elapsed();
+    let plain_text: Vec<i64> = __gen_random_array1__(degree as i64, 2).to_vec();
 
     println!("\n======== MODULE-LWE ========");
-    println!("Parameter size: {}", k);
-    println!("Time KeyGen: {:.6}s", keygen_time.as_secs_f64());
-    println!("Time Enc: {:.6}ms", enc_time.as_secs_f64() * 1000.0);
-    println!("Time Dec: {:.6}ms", dec_time.as_secs_f64() * 1000.0);
+    println!("Rank: {}, Ring degree: {}", rank, degree);
+
+    benchmark::<module::ModuleLwe>(&params, &plain_text);
+
+    let trials = 10000;
+    let rate = module::failure_rate(trials, &params, &mut rand::thread_rng());
+    println!("Decryption failure rate ({} bits): {}", trials, rate);
+    println!("Success: {}", rate == 0.0);
+}
+
+// ML-KEM round trip, implicit rejection and the vendored known-answer vectors in kat/
+fn mlkem(name: &str, params: &mlkem::SecurityParameters, kat_file: &str) {
+    println!("\n======== {} ========", name);
+    let mut rng = rand::thread_rng();
+
+    let start: Instant = Instant::now();
+    let (ek, dk) = mlkem::keygen(params, &mut rng);
+    println!("Time KeyGen: {:?}", start.elapsed());
+
+    let start: Instant = Instant::now();
+    let (key, cipher_text) = mlkem::encaps(params, &ek, &mut rng).unwrap();
+    println!("Time Encaps: {:?}", start.elapsed());
+
+    let start: Instant = Instant::now();
+    let decapsulated = mlkem::decaps(params, &dk, &cipher_text);
+    println!("Time Decaps: {:?}", start.elapsed());
+
+    println!(
+        "Sizes (ek, dk, ct): {}, {}, {}",
+        ek.len(),
+        dk.len(),
+        cipher_text.len()
+    );
+    println!("Success: {}", decapsulated == Some(key));
+
+    // A tampered ciphertext decapsulates to the pseudorandom rejection key J(z || c)
+    let mut tampered = cipher_text.clone();
+    tampered[0] ^= 1;
+    let rejected = mlkem::decaps(params, &dk, &tampered);
+    println!(
+        "Implicit rejection: {}",
+        rejected.is_some() && rejected != Some(key)
+    );
+
+    match std::fs::read_to_string(format!("{}/kat/{}", env!("CARGO_MANIFEST_DIR"), kat_file)) {
+        Ok(contents) => {
+            let (passed, failed) = mlkem::run_kat(params, &contents);
+            println!("KAT {}: {} passed, {} failed", kat_file, passed, failed);
+            println!("Success: {}", passed > 0 && failed == 0);
+        }
+        Err(_) => println!("KAT {}: not found, skipped", kat_file),
+    }
+}
+
+// Fujisaki-Okamoto KEM over a CPA scheme: round trip, and a ciphertext of the same message
+// under fresh (not derived) coins must be rejected
+fn fo_kem<S: DeterministicEncryption>(name: &str, params: &S::Params) {
+    println!("\n======== FO KEM: {} ========", name);
+    let mut rng = rand::thread_rng();
+    let (pub_key, priv_key) = fo::keygen::<S>(params, &mut rng);
+
+    let start: Instant = Instant::now();
+    let (key, cipher_text) = fo::encaps::<S>(params, &pub_key, &mut rng);
+    println!("Time Encaps: {:?}", start.elapsed());
+
+    let start: Instant = Instant::now();
+    let decapsulated = fo::decaps::<S>(params, &pub_key, &priv_key, &cipher_text);
+    println!("Time Decaps: {:?}", start.elapsed());
+    println!("Success: {}", decapsulated == key);
+
+    let message: Vec<u8> = (0..S::message_bits(params) / 8)
+        .map(|_| rng.gen())
+        .collect();
+    let (key, _) = fo::encaps_internal::<S>(params, &pub_key, &message);
+    let forged = S::encrypt(params, &pub_key, &bytes_to_bits(&message), &mut rng);
+    let rejected = fo::decaps::<S>(params, &pub_key, &priv_key, &forged);
+    println!(
+        "Implicit rejection: {}",
+        rejected != key && rejected == fo::decaps::<S>(params, &pub_key, &priv_key, &forged)
+    );
+}
+
+// Random prime of exactly `bits` bits
+fn random_prime(bits: u32, state: &mut RandState) -> Integer {
+    let mut candidate = Integer::from(Integer::random_bits(bits, state));
+    candidate.set_bit(bits - 1, true);
+    candidate.next_prime()
+}
+
+// RSA modulus N = pq with gcd(e, phi(N)) = 1. Returns (N, p, q)
+fn rsa_modulus(bits: u32, e: u32, state: &mut RandState) -> (Integer, Integer, Integer) {
+    loop {
+        let p = random_prime(bits / 2, state);
+        let q = random_prime(bits - bits / 2, state);
+        let phi = Integer::from(&p - 1) * Integer::from(&q - 1);
+        if p != q && phi.gcd(&Integer::from(e)) == 1 {
+            return (Integer::from(&p * &q), p, q);
+        }
+    }
+}
+
+// RSA key with a random private exponent of exactly `d_bits` bits. Returns (N, e, d)
+fn weak_rsa_key(bits: u32, d_bits: u32, state: &mut RandState) -> (Integer, Integer, Integer) {
+    loop {
+        let p = random_prime(bits / 2, state);
+        let q = random_prime(bits - bits / 2, state);
+        let phi = Integer::from(&p - 1) * Integer::from(&q - 1);
+
+        let mut d = Integer::from(Integer::random_bits(d_bits, state));
+        d.set_bit(d_bits - 1, true);
+        if let Ok(e) = d.clone().invert(&phi) {
+            return (p * q, e, d);
+        }
+    }
+}
+
+// Lattice attacks on RSA with e = 3 against locally generated keys
+fn coppersmith_rsa() {
+    let mut state = RandState::new();
+    state.seed(&Integer::from(rand::thread_rng().gen::<u64>()));
+    let e = 3;
+
+    // m^e < N, the ciphertext never wraps around
+    let (n, _, _) = rsa_modulus(1024, e, &mut state);
+    let message = Integer::from(Integer::random_bits(300, &mut state));
+    let cipher_text = message.clone().pow_mod(&Integer::from(e), &n).unwrap();
+    let result = coppersmith::low_public_exponent_attack(&n, e as usize, &cipher_text);
+    println!("\n======== LOW EXPONENT (N: 1024 bits) ========");
+    println!("Success: {}", result == Some(message));
+
+    // Everything but the last quarter of the message is known. The 2048-bit case is an
+    // ignored test in coppersmith.rs, it takes over a minute
+    let bits = 1024;
+    let (n, _, _) = rsa_modulus(bits, e, &mut state);
+    let unknown_bits = bits / 4;
+    let secret = Integer::from(Integer::random_bits(unknown_bits, &mut state));
+    let known =
+        Integer::from(Integer::random_bits(bits - 8, &mut state)) >> unknown_bits << unknown_bits;
+    let message = Integer::from(&known + &secret);
+    let cipher_text = message.clone().pow_mod(&Integer::from(e), &n).unwrap();
+
+    println!("\n======== STEREOTYPED MESSAGE (N: {} bits) ========", bits);
+    println!("Unknown bits: {}", unknown_bits);
+    let start: Instant = Instant::now();
+    let result =
+        coppersmith::stereotyped_message_attack(&n, e as usize, &cipher_text, &known, unknown_bits);
+    println!("Time Attack: {:?}", start.elapsed());
+    println!("Success: {}", result == Some(message));
+
+    // Factor N from 60% of the bits of p. 40% is below the N^(1/4) bound, no lattice is built
+    for bits in [512, 1024] {
+        let (n, p, _) = rsa_modulus(bits, 65537, &mut state);
+        let prime_bits = bits / 2;
+        for exposure in [
+            coppersmith::Exposure::MostSignificant,
+            coppersmith::Exposure::LeastSignificant,
+        ] {
+            println!(
+                "\n======== PARTIAL KEY EXPOSURE (N: {} bits, {:?}) ========",
+                bits, exposure
+            );
+            for (known_bits, expected) in [(prime_bits * 3 / 5, true), (prime_bits * 2 / 5, false)]
+            {
+                let known = match exposure {
+                    coppersmith::Exposure::MostSignificant => {
+                        Integer::from(&p >> (prime_bits - known_bits))
+                    }
+                    coppersmith::Exposure::LeastSignificant => p.clone().keep_bits(known_bits),
+                };
+
+                let start: Instant = Instant::now();
+                let result =
+                    coppersmith::partial_key_exposure_attack(&n, &known, known_bits, exposure);
+                println!("Time Attack: {:?}", start.elapsed());
+                println!(
+                    "Known bits: {}, Lattice dimension: {}, Factored: {}",
+                    known_bits,
+                    result.dimension,
+                    result.factors.is_some()
+                );
+                let success = if expected {
+                    matches!(result.factors, Some((x, y)) if x == p || y == p)
+                } else {
+                    result.factors.is_none() && result.dimension == 0
+                };
+                println!("Success: {}", success);
+            }
+        }
+    }
+
+    // Related messages under one 1024-bit key with e = 3
+    let (n, _, _) = rsa_modulus(1024, e, &mut state);
+    let encrypt = |message: &Integer| message.clone().pow_mod(&Integer::from(e), &n).unwrap();
+
+    println!("\n======== FRANKLIN-REITER (N: 1024 bits) ========");
+    let message = Integer::from(Integer::random_bits(1000, &mut state));
+    let (a, b) = (
+        Integer::from(Integer::random_bits(1000, &mut state)),
+        Integer::from(Integer::random_bits(1000, &mut state)),
+    );
+    let related = (Integer::from(&a * &message) + &b).rem_euc(&n);
+    let start: Instant = Instant::now();
+    let result = coppersmith::franklin_reiter_attack(
+        &n,
+        e as usize,
+        &encrypt(&message),
+        &encrypt(&related),
+        &a,
+        &b,
+    );
+    println!("Time Attack: {:?}", start.elapsed());
+    println!("Success: {}", result == Some(message));
+
+    // Same message, two random 32-bit paddings
+    println!("\n======== SHORT PAD (N: 1024 bits) ========");
+    let pad_bits = 32;
+    let message = Integer::from(Integer::random_bits(1024 - 8 - pad_bits, &mut state)) << pad_bits;
+    let first = message.clone() + Integer::from(Integer::random_bits(pad_bits, &mut state));
+    let second = message.clone() + Integer::from(Integer::random_bits(pad_bits, &mut state));
+    let start: Instant = Instant::now();
+    let result = coppersmith::short_pad_attack(
+        &n,
+        e as usize,
+        &encrypt(&first),
+        &encrypt(&second),
+        pad_bits,
+    );
+    println!("Time Attack: {:?}", start.elapsed());
+    println!("Pad bits: {}", pad_bits);
+    println!("Success: {}", result == Some((first, second)));
+
+    // One message to several 1024-bit recipients with e = 3
+    let recipients: Vec<Integer> = (0..e + 1)
+        .map(|_| rsa_modulus(1024, e, &mut state).0)
+        .collect();
+    let message = Integer::from(Integer::random_bits(800, &mut state));
+
+    println!("\n======== HASTAD BROADCAST (N: 1024 bits) ========");
+    let cipher_texts: Vec<Integer> = recipients[..e as usize]
+        .iter()
+        .map(|n_i| message.clone().pow_mod(&Integer::from(e), n_i).unwrap())
+        .collect();
+    let start: Instant = Instant::now();
+    let result =
+        coppersmith::hastad_broadcast_attack(e as usize, &cipher_texts, &recipients[..e as usize]);
+    println!("Time Attack: {:?}", start.elapsed());
+    println!("Recipients: {}", e);
+    println!("Success: {}", result == Some(message.clone()));
+
+    // Each recipient gets a_i m + b_i. One more recipient leaves room for a small lattice
+    println!("\n======== HASTAD LINEAR PADDING (N: 1024 bits) ========");
+    let paddings: Vec<(Integer, Integer)> = recipients
+        .iter()
+        .map(|n_i| {
+            (
+                Integer::from(Integer::random_bits(1024, &mut state)).rem_euc(n_i),
+                Integer::from(Integer::random_bits(1024, &mut state)).rem_euc(n_i),
+            )
+        })
+        .collect();
+    let cipher_texts: Vec<Integer> = recipients
+        .iter()
+        .zip(paddings.iter())
+        .map(|(n_i, (a, b))| {
+            (Integer::from(a * &message) + b)
+                .pow_mod(&Integer::from(e), n_i)
+                .unwrap()
+        })
+        .collect();
+    let start: Instant = Instant::now();
+    let result =
+        coppersmith::hastad_padding_attack(e as usize, &cipher_texts, &recipients, &paddings, 800);
+    println!("Time Attack: {:?}", start.elapsed());
+    println!("Recipients: {}", recipients.len());
+    println!("Success: {}", result == Some(message));
+
+    // Small private exponents. Wiener below N^0.25, Boneh-Durfee past it where Wiener fails
+    println!("\n======== WIENER (N: 1024 bits) ========");
+    let (n, e, d) = weak_rsa_key(1024, 240, &mut state);
+    let start: Instant = Instant::now();
+    let result = coppersmith::wiener_attack(&n, &e);
+    println!("Time Attack: {:?}", start.elapsed());
+    println!("d: {} bits", d.significant_bits());
+    println!(
+        "Success: {}",
+        matches!(result, Some(key) if key.d == d && Integer::from(&key.p * &key.q) == n)
+    );
+
+    // delta = 0.26 with m = 4. delta = 0.27 needs m = 6 and about two minutes, it is an ignored
+    // test in coppersmith.rs
+    println!("\n======== BONEH-DURFEE (N: 1024 bits, m: 4) ========");
+    let (n, e, d) = weak_rsa_key(1024, 266, &mut state);
+    println!("d: {} bits", d.significant_bits());
+    println!("Wiener: {}", coppersmith::wiener_attack(&n, &e).is_some());
+    let start: Instant = Instant::now();
+    let result = coppersmith::boneh_durfee_attack(&n, &e, 0.26, 4);
+    println!("Time Attack: {:?}", start.elapsed());
+    println!(
+        "Success: {}",
+        matches!(result, Some(key) if key.d == d && Integer::from(&key.p * &key.q) == n)
+    );
 }
 
 fn main() {
+    println!("======================");
+    println!("--- Samplers ---");
+    println!("======================");
+    samplers(100000);
+
     // Enable the one you want to test
     for lengths in 1..=4 {
         println!("======================");
@@ -183,17 +882,55 @@ fn main() {
         println!("======================");
         regev(128 * lengths);
     }
+    regev_stream(256);
+    regev_failure_rate(10000);
+    lwr(10000);
 
     // Test Module LWE
-    modulwe(128);
-    modulwe(256);
-    modulwe(512);
+    modulwe(2, 256);
+    modulwe(3, 256);
+    modulwe(4, 256);
+
+    // ML-KEM (FIPS 203)
+    mlkem("ML-KEM-512", &mlkem::ml_kem_512(), "ml-kem-512.kat");
+    mlkem("ML-KEM-768", &mlkem::ml_kem_768(), "ml-kem-768.kat");
+    mlkem("ML-KEM-1024", &mlkem::ml_kem_1024(), "ml-kem-1024.kat");
 
     lizard();
+    rlizard();
     println!("\n======================\n");
     println!("--- Ring-LWE (512) ---");
     println!("======================");
     ringlwe(512);
+    ringlwe_message(b"Implementing Quantum-Resistant Cryptography with Lattices: Ring-LWE encrypts this message in 512-bit blocks.");
+    ring_arithmetic(10);
+
+    // CCA-secure KEMs from the CPA schemes
+    fo_kem::<regev::Regev>("Regev", &regev::setup());
+    fo_kem::<ringlwe::RingLwe>("Ring-LWE", &ringlwe::setup());
+    fo_kem::<lizard::Lizard>("Lizard", &lizard::setup());
+    fo_kem::<lizard::Lizard>(
+        "Lizard, t = 4",
+        &lizard::SecurityParameters {
+            name: "CATEGORY1_N536, t = 4",
+            log_t: 2,
+            ..lizard::setup()
+        },
+    );
+    fo_kem::<rlizard::RLizard>("RLizard", &rlizard::setup());
+    fo_kem::<module::ModuleLwe>(
+        "Module-LWE",
+        &module::SecurityParameters {
+            rank: 2,
+            degree: 256,
+            modulo: 3329,
+        },
+    );
+
+    println!("\n======================");
+    println!("--- NTRU (167) ---");
+    println!("======================");
+    ntru();
 
     println!("\n======================");
     println!("--- Interactive Multi-Party KEP ---");
@@ -202,21 +939,13 @@ fn main() {
     let num_users = 4;
     multiparty::benchmark_key_exchange(num_users);
 
-    // // Coppersmith Testing
-    // let n = Integer::from(77); // Public modulus
-    // let e = 3; // Public exponent
-    // let c = Integer::from(64); // Ciphertext
-    // println!("=======================");
-    // println!("--- Coppersmith RSA ---");
-    // println!("=======================");
-    // match coppersmith::low_public_exponent_attack(&n, e, &c) {
-    //     Some(plaintext) => {
-    //         println!("Recovered plaintext: {}", plaintext);
-    //         println!("N, E: {}, {}", n, e);
-    //         println!("Ciphertext: {}", c);
-    //     }
-    //     None => {
-    //         println!("No plaintext found. Ensure conditions are met (e.g., m^e < N).");
-    //     }
-    // }
+    println!("\n======================");
+    println!("--- Known Answers ---");
+    println!("======================");
+    known_answers();
+
+    println!("\n=======================");
+    println!("--- Coppersmith RSA ---");
+    println!("=======================");
+    coppersmith_rsa();
 }
@@ -0,0 +1,579 @@
+// ML-KEM (FIPS 203), the standardized Kyber KEM.
+// Same Module-LWE structure as module.rs, but over the fixed ring Z_3329[X]/(X^256 + 1) with
+// keys kept in the NTT domain, compressed ciphertexts, a fixed byte encoding and the
+// Fujisaki-Okamoto transform with implicit rejection on top of the K-PKE scheme.
+use rand::{CryptoRng, RngCore};
+use sha3::digest::{Digest, ExtendableOutput, Update, XofReader};
+use sha3::{Sha3_256, Sha3_512, Shake128, Shake256};
+
+const N: usize = 256;
+const Q: i64 = 3329;
+// 17 is a primitive 256-th root of unity mod Q
+const ZETA: i64 = 17;
+// 128^-1 mod Q
+const N_INV: i64 = 3303;
+
+const ZETAS: [i64; 128] = zeta_table(false);
+const GAMMAS: [i64; 128] = zeta_table(true);
+
+type Polynomial = [i64; N];
+
+pub struct SecurityParameters {
+    pub rank: usize, // k
+    pub eta1: usize,
+    pub eta2: usize,
+    pub du: u32,
+    pub dv: u32,
+}
+
+pub fn ml_kem_512() -> SecurityParameters {
+    SecurityParameters {
+        rank: 2,
+        eta1: 3,
+        eta2: 2,
+        du: 10,
+        dv: 4,
+    }
+}
+
+pub fn ml_kem_768() -> SecurityParameters {
+    SecurityParameters {
+        rank: 3,
+        eta1: 2,
+        eta2: 2,
+        du: 10,
+        dv: 4,
+    }
+}
+
+pub fn ml_kem_1024() -> SecurityParameters {
+    SecurityParameters {
+        rank: 4,
+        eta1: 2,
+        eta2: 2,
+        du: 11,
+        dv: 5,
+    }
+}
+
+// Sizes in bytes
+pub fn encapsulation_key_size(params: &SecurityParameters) -> usize {
+    384 * params.rank + 32
+}
+
+pub fn decapsulation_key_size(params: &SecurityParameters) -> usize {
+    768 * params.rank + 96
+}
+
+pub fn ciphertext_size(params: &SecurityParameters) -> usize {
+    32 * (params.du as usize * params.rank + params.dv as usize)
+}
+
+// ZETAS[i] = 17^BitRev7(i), GAMMAS[i] = 17^(2 BitRev7(i) + 1)
+const fn zeta_table(gamma: bool) -> [i64; 128] {
+    let mut table = [0i64; 128];
+    let mut i = 0;
+    while i < 128 {
+        let rev = ((i as u8).reverse_bits() >> 1) as i64;
+        let exp = if gamma { 2 * rev + 1 } else { rev };
+        let mut value = 1;
+        let mut e = 0;
+        while e < exp {
+            value = value * ZETA % Q;
+            e += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+}
+
+// Hash functions H, G, J and PRF from FIPS 203 section 4.1
+fn hash_h(input: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(input).into()
+}
+
+fn hash_g(input: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let output = Sha3_512::digest(input);
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    a.copy_from_slice(&output[..32]);
+    b.copy_from_slice(&output[32..]);
+    (a, b)
+}
+
+fn hash_j(input: &[u8]) -> [u8; 32] {
+    let mut hasher = Shake256::default();
+    hasher.update(input);
+    let mut output = [0u8; 32];
+    hasher.finalize_xof().read(&mut output);
+    output
+}
+
+fn prf(eta: usize, seed: &[u8; 32], nonce: u8) -> Vec<u8> {
+    let mut hasher = Shake256::default();
+    hasher.update(seed);
+    hasher.update(&[nonce]);
+    let mut output: Vec<u8> = vec![0; 64 * eta];
+    hasher.finalize_xof().read(&mut output);
+    output
+}
+
+// Compress_d(x) = round(2^d / q * x) mod 2^d
+fn compress(poly: &Polynomial, d: u32) -> Polynomial {
+    let mut result = [0i64; N];
+    for i in 0..N {
+        result[i] = (((poly[i] << (d + 1)) + Q) / (2 * Q)) & ((1 << d) - 1);
+    }
+    result
+}
+
+// Decompress_d(y) = round(q / 2^d * y)
+fn decompress(poly: &Polynomial, d: u32) -> Polynomial {
+    let mut result = [0i64; N];
+    for i in 0..N {
+        result[i] = (poly[i] * 2 * Q + (1 << d)) >> (d + 1);
+    }
+    result
+}
+
+// ByteEncode_d: 256 d-bit integers into 32d bytes, least significant bit first
+fn byte_encode(poly: &Polynomial, d: u32) -> Vec<u8> {
+    let d = d as usize;
+    let mut bytes: Vec<u8> = vec![0; 32 * d];
+    for i in 0..N {
+        let mut a = poly[i];
+        for j in 0..d {
+            let bit = i * d + j;
+            bytes[bit / 8] |= ((a & 1) as u8) << (bit % 8);
+            a >>= 1;
+        }
+    }
+    bytes
+}
+
+// ByteDecode_d, reducing mod q when d = 12
+fn byte_decode(bytes: &[u8], d: u32) -> Polynomial {
+    let modulo = if d < 12 { 1 << d } else { Q };
+    let d = d as usize;
+    let mut poly = [0i64; N];
+    for i in 0..N {
+        let mut a = 0;
+        for j in 0..d {
+            let bit = i * d + j;
+            a |= (((bytes[bit / 8] >> (bit % 8)) & 1) as i64) << j;
+        }
+        poly[i] = a % modulo;
+    }
+    poly
+}
+
+// SampleNTT: rejection sampling of a uniform NTT-domain polynomial from SHAKE128(seed || j || i)
+fn sample_ntt(seed: &[u8; 32], j: u8, i: u8) -> Polynomial {
+    let mut hasher = Shake128::default();
+    hasher.update(seed);
+    hasher.update(&[j, i]);
+    let mut reader = hasher.finalize_xof();
+
+    let mut poly = [0i64; N];
+    let mut count = 0;
+    let mut c = [0u8; 3];
+    while count < N {
+        reader.read(&mut c);
+        let d1 = c[0] as i64 + 256 * (c[1] as i64 % 16);
+        let d2 = c[1] as i64 / 16 + 16 * c[2] as i64;
+        if d1 < Q {
+            poly[count] = d1;
+            count += 1;
+        }
+        if d2 < Q && count < N {
+            poly[count] = d2;
+            count += 1;
+        }
+    }
+    poly
+}
+
+// SamplePolyCBD_eta from 64 eta bytes
+fn sample_cbd(bytes: &[u8], eta: usize) -> Polynomial {
+    let bit = |index: usize| ((bytes[index / 8] >> (index % 8)) & 1) as i64;
+
+    let mut poly = [0i64; N];
+    for i in 0..N {
+        let mut x = 0;
+        let mut y = 0;
+        for j in 0..eta {
+            x += bit(2 * i * eta + j);
+            y += bit(2 * i * eta + eta + j);
+        }
+        poly[i] = (x - y).rem_euclid(Q);
+    }
+    poly
+}
+
+// Forward NTT, 7 layers: the result is 128 degree-1 residues mod X^2 - GAMMAS[i]
+fn ntt(poly: &Polynomial) -> Polynomial {
+    let mut f = *poly;
+    let mut i = 1;
+    let mut len = 128;
+    while len >= 2 {
+        for start in (0..N).step_by(2 * len) {
+            let zeta = ZETAS[i];
+            i += 1;
+            for j in start..start + len {
+                let t = zeta * f[j + len] % Q;
+                f[j + len] = (f[j] - t).rem_euclid(Q);
+                f[j] = (f[j] + t) % Q;
+            }
+        }
+        len /= 2;
+    }
+    f
+}
+
+fn ntt_inverse(poly: &Polynomial) -> Polynomial {
+    let mut f = *poly;
+    let mut i = 127;
+    let mut len = 2;
+    while len <= 128 {
+        for start in (0..N).step_by(2 * len) {
+            let zeta = ZETAS[i];
+            i -= 1;
+            for j in start..start + len {
+                let t = f[j];
+                f[j] = (t + f[j + len]) % Q;
+                f[j + len] = zeta * (f[j + len] - t).rem_euclid(Q) % Q;
+            }
+        }
+        len *= 2;
+    }
+    f.iter_mut().for_each(|x| *x = *x * N_INV % Q);
+    f
+}
+
+// Product of two NTT-domain polynomials, pairwise in Z_q[X]/(X^2 - gamma)
+fn multiply_ntt(f: &Polynomial, g: &Polynomial) -> Polynomial {
+    let mut h = [0i64; N];
+    for i in 0..128 {
+        let (a0, a1, b0, b1) = (f[2 * i], f[2 * i + 1], g[2 * i], g[2 * i + 1]);
+        h[2 * i] = (a0 * b0 + a1 * b1 % Q * GAMMAS[i]) % Q;
+        h[2 * i + 1] = (a0 * b1 + a1 * b0) % Q;
+    }
+    h
+}
+
+fn add(f: &Polynomial, g: &Polynomial) -> Polynomial {
+    let mut h = [0i64; N];
+    for i in 0..N {
+        h[i] = (f[i] + g[i]) % Q;
+    }
+    h
+}
+
+fn sub(f: &Polynomial, g: &Polynomial) -> Polynomial {
+    let mut h = [0i64; N];
+    for i in 0..N {
+        h[i] = (f[i] - g[i]).rem_euclid(Q);
+    }
+    h
+}
+
+// A_hat[i][j] = SampleNTT(rho || j || i)
+fn generate_matrix(rho: &[u8; 32], k: usize) -> Vec<Vec<Polynomial>> {
+    (0..k)
+        .map(|i| (0..k).map(|j| sample_ntt(rho, j as u8, i as u8)).collect())
+        .collect()
+}
+
+// K-PKE.KeyGen: returns (ek_pke, dk_pke)
+fn pke_keygen(params: &SecurityParameters, d: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+    let k = params.rank;
+    let mut seed = d.to_vec();
+    seed.push(k as u8);
+    let (rho, sigma) = hash_g(&seed);
+
+    let a_hat = generate_matrix(&rho, k);
+
+    let mut nonce = 0u8;
+    let mut s_hat: Vec<Polynomial> = Vec::with_capacity(k);
+    for _ in 0..k {
+        s_hat.push(ntt(&sample_cbd(
+            &prf(params.eta1, &sigma, nonce),
+            params.eta1,
+        )));
+        nonce += 1;
+    }
+    let mut e_hat: Vec<Polynomial> = Vec::with_capacity(k);
+    for _ in 0..k {
+        e_hat.push(ntt(&sample_cbd(
+            &prf(params.eta1, &sigma, nonce),
+            params.eta1,
+        )));
+        nonce += 1;
+    }
+
+    // t_hat = A_hat * s_hat + e_hat
+    let mut ek: Vec<u8> = Vec::with_capacity(encapsulation_key_size(params));
+    let mut dk: Vec<u8> = Vec::with_capacity(384 * k);
+    for i in 0..k {
+        let mut t_hat = e_hat[i];
+        for j in 0..k {
+            t_hat = add(&t_hat, &multiply_ntt(&a_hat[i][j], &s_hat[j]));
+        }
+        ek.extend(byte_encode(&t_hat, 12));
+        dk.extend(byte_encode(&s_hat[i], 12));
+    }
+    ek.extend_from_slice(&rho);
+
+    (ek, dk)
+}
+
+// K-PKE.Encrypt of a 32-byte message under randomness r
+fn pke_encrypt(params: &SecurityParameters, ek: &[u8], m: &[u8; 32], r: &[u8; 32]) -> Vec<u8> {
+    let k = params.rank;
+    let t_hat: Vec<Polynomial> = (0..k)
+        .map(|i| byte_decode(&ek[384 * i..384 * (i + 1)], 12))
+        .collect();
+    let mut rho = [0u8; 32];
+    rho.copy_from_slice(&ek[384 * k..384 * k + 32]);
+
+    let a_hat = generate_matrix(&rho, k);
+
+    let mut nonce = 0u8;
+    let mut y_hat: Vec<Polynomial> = Vec::with_capacity(k);
+    for _ in 0..k {
+        y_hat.push(ntt(&sample_cbd(&prf(params.eta1, r, nonce), params.eta1)));
+        nonce += 1;
+    }
+    let mut e1: Vec<Polynomial> = Vec::with_capacity(k);
+    for _ in 0..k {
+        e1.push(sample_cbd(&prf(params.eta2, r, nonce), params.eta2));
+        nonce += 1;
+    }
+    let e2 = sample_cbd(&prf(params.eta2, r, nonce), params.eta2);
+
+    let mut c: Vec<u8> = Vec::with_capacity(ciphertext_size(params));
+
+    // u = NTT^-1(A_hat^T * y_hat) + e1
+    for i in 0..k {
+        let mut sum = [0i64; N];
+        for j in 0..k {
+            sum = add(&sum, &multiply_ntt(&a_hat[j][i], &y_hat[j]));
+        }
+        let u = add(&ntt_inverse(&sum), &e1[i]);
+        c.extend(byte_encode(&compress(&u, params.du), params.du));
+    }
+
+    // v = NTT^-1(t_hat^T * y_hat) + e2 + mu
+    let mut sum = [0i64; N];
+    for i in 0..k {
+        sum = add(&sum, &multiply_ntt(&t_hat[i], &y_hat[i]));
+    }
+    let mu = decompress(&byte_decode(m, 1), 1);
+    let v = add(&add(&ntt_inverse(&sum), &e2), &mu);
+    c.extend(byte_encode(&compress(&v, params.dv), params.dv));
+
+    c
+}
+
+// K-PKE.Decrypt
+fn pke_decrypt(params: &SecurityParameters, dk: &[u8], c: &[u8]) -> [u8; 32] {
+    let k = params.rank;
+    let du = params.du as usize;
+    let split = 32 * du * k;
+
+    // w = v' - NTT^-1(s_hat^T * NTT(u'))
+    let mut sum = [0i64; N];
+    for i in 0..k {
+        let u = decompress(
+            &byte_decode(&c[32 * du * i..32 * du * (i + 1)], params.du),
+            params.du,
+        );
+        let s_hat = byte_decode(&dk[384 * i..384 * (i + 1)], 12);
+        sum = add(&sum, &multiply_ntt(&s_hat, &ntt(&u)));
+    }
+    let v = decompress(&byte_decode(&c[split..], params.dv), params.dv);
+    let w = sub(&v, &ntt_inverse(&sum));
+
+    let mut m = [0u8; 32];
+    m.copy_from_slice(&byte_encode(&compress(&w, 1), 1));
+    m
+}
+
+// Deterministic ML-KEM.KeyGen_internal: returns (ek, dk)
+pub fn keygen_internal(
+    params: &SecurityParameters,
+    d: &[u8; 32],
+    z: &[u8; 32],
+) -> (Vec<u8>, Vec<u8>) {
+    let (ek, dk_pke) = pke_keygen(params, d);
+
+    // dk = dk_pke || ek || H(ek) || z
+    let mut dk = dk_pke;
+    dk.extend_from_slice(&ek);
+    dk.extend_from_slice(&hash_h(&ek));
+    dk.extend_from_slice(z);
+
+    (ek, dk)
+}
+
+// Deterministic ML-KEM.Encaps_internal: returns (shared key, ciphertext)
+pub fn encaps_internal(
+    params: &SecurityParameters,
+    ek: &[u8],
+    m: &[u8; 32],
+) -> ([u8; 32], Vec<u8>) {
+    let mut seed = m.to_vec();
+    seed.extend_from_slice(&hash_h(ek));
+    let (key, r) = hash_g(&seed);
+
+    (key, pke_encrypt(params, ek, m, &r))
+}
+
+// ML-KEM.Decaps_internal: re-encrypts and falls back to J(z || c) on mismatch
+pub fn decaps_internal(params: &SecurityParameters, dk: &[u8], c: &[u8]) -> [u8; 32] {
+    let k = params.rank;
+    let dk_pke = &dk[..384 * k];
+    let ek = &dk[384 * k..768 * k + 32];
+    let h = &dk[768 * k + 32..768 * k + 64];
+    let z = &dk[768 * k + 64..768 * k + 96];
+
+    let m = pke_decrypt(params, dk_pke, c);
+    let mut seed = m.to_vec();
+    seed.extend_from_slice(h);
+    let (key, r) = hash_g(&seed);
+
+    let mut rejection_input = z.to_vec();
+    rejection_input.extend_from_slice(c);
+    let rejection_key = hash_j(&rejection_input);
+
+    let c_prime = pke_encrypt(params, ek, &m, &r);
+
+    // Constant-time select between key and rejection_key
+    let diff = c
+        .iter()
+        .zip(c_prime.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    let mask = ((diff as u16).wrapping_neg() >> 8) as u8; // 0xff if the ciphertexts differ
+    let mut shared = [0u8; 32];
+    for i in 0..32 {
+        shared[i] = (key[i] & !mask) | (rejection_key[i] & mask);
+    }
+    shared
+}
+
+// ML-KEM.KeyGen: returns (encapsulation key, decapsulation key)
+pub fn keygen(
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (Vec<u8>, Vec<u8>) {
+    let mut d = [0u8; 32];
+    let mut z = [0u8; 32];
+    rng.fill_bytes(&mut d);
+    rng.fill_bytes(&mut z);
+    keygen_internal(params, &d, &z)
+}
+
+// ML-KEM.Encaps: None if the encapsulation key fails the type and modulus checks
+pub fn encaps(
+    params: &SecurityParameters,
+    ek: &[u8],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Option<([u8; 32], Vec<u8>)> {
+    if ek.len() != encapsulation_key_size(params) {
+        return None;
+    }
+    for i in 0..params.rank {
+        let chunk = &ek[384 * i..384 * (i + 1)];
+        if byte_encode(&byte_decode(chunk, 12), 12) != chunk {
+            return None;
+        }
+    }
+
+    let mut m = [0u8; 32];
+    rng.fill_bytes(&mut m);
+    Some(encaps_internal(params, ek, &m))
+}
+
+// ML-KEM.Decaps: None if the ciphertext or decapsulation key fail the input checks
+pub fn decaps(params: &SecurityParameters, dk: &[u8], c: &[u8]) -> Option<[u8; 32]> {
+    let k = params.rank;
+    if c.len() != ciphertext_size(params) || dk.len() != decapsulation_key_size(params) {
+        return None;
+    }
+    if hash_h(&dk[384 * k..768 * k + 32]) != dk[768 * k + 32..768 * k + 64] {
+        return None;
+    }
+
+    Some(decaps_internal(params, dk, c))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+// Run known-answer vectors in the `name = hex` format, one blank-line separated block per test.
+// A block is checked for every operation its fields cover, as in the ACVP test types:
+// d, z -> ek, dk (keyGen); ek, m -> c, k (encapsulation); dk, c -> k (decapsulation).
+// The NIST .rsp names pk, sk, msg, ct, ss are accepted too, and ct_n, ss_n give a modified
+// ciphertext whose decapsulation must return the implicit rejection key J(z || c).
+// Lines starting with '#' are comments. Returns (passed, failed).
+pub fn run_kat(params: &SecurityParameters, contents: &str) -> (usize, usize) {
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for block in contents.split("\n\n") {
+        let field = |names: &[&str]| -> Option<Vec<u8>> {
+            block
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| line.split_once('='))
+                .find(|(key, _)| names.contains(&key.trim()))
+                .and_then(|(_, value)| decode_hex(value.trim()))
+        };
+
+        let ek = field(&["ek", "pk"]);
+        let dk = field(&["dk", "sk"]);
+        let c = field(&["c", "ct"]);
+        let k = field(&["k", "ss"]);
+        let mut checks: Vec<bool> = Vec::new();
+
+        if let (Some(d), Some(z), Some(ek), Some(dk)) = (field(&["d"]), field(&["z"]), &ek, &dk) {
+            checks.push(match (<[u8; 32]>::try_from(d), <[u8; 32]>::try_from(z)) {
+                (Ok(d), Ok(z)) => keygen_internal(params, &d, &z) == (ek.clone(), dk.clone()),
+                _ => false,
+            });
+        }
+        if let (Some(ek), Some(m), Some(c), Some(k)) = (&ek, field(&["m", "msg"]), &c, &k) {
+            checks.push(match <[u8; 32]>::try_from(m) {
+                Ok(m) => {
+                    let (k_out, c_out) = encaps_internal(params, ek, &m);
+                    k_out[..] == k[..] && c_out == *c
+                }
+                Err(_) => false,
+            });
+        }
+        if let (Some(dk), Some(c), Some(k)) = (&dk, &c, &k) {
+            checks.push(decaps(params, dk, c).map(|x| x.to_vec()) == Some(k.clone()));
+        }
+        if let (Some(dk), Some(c), Some(k)) = (&dk, field(&["ct_n"]), field(&["ss_n"])) {
+            checks.push(decaps(params, dk, &c).map(|x| x.to_vec()) == Some(k));
+        }
+
+        if checks.is_empty() {
+            continue;
+        }
+        if checks.iter().all(|&ok| ok) {
+            passed += 1;
+        } else {
+            failed += 1;
+        }
+    }
+    (passed, failed)
+}
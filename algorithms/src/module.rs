@@ -1,59 +1,261 @@
-use nalgebra::{DMatrix, DVector};
-use rand::{thread_rng, Rng};
+// Module-LWE over R_q = Z_q[X]/(X^n + 1)
+// Public matrix A is k x k over R_q, secrets and errors are vectors of small ring elements.
+// Each ciphertext carries n message bits, one per coefficient.
+// Ring products go through the NTT when q is an odd prime, e.g. q = 3329 with n = 256.
+use rand::{CryptoRng, Rng, RngCore};
 use std::env;
 use std::time::Instant;
 
-fn sample_ring_vector(n: usize, q: i64) -> DVector<i64> {
-    DVector::from_fn(n, |_, _| thread_rng().gen_range(0..q))
+use crate::ntt::{self, NttTables};
+use crate::pke::{coefficients_to_bytes, DeterministicEncryption, PublicKeyEncryption};
+use crate::ringlwe::RingElement;
+use crate::sampler::{BinomialSampler, Sampler};
+
+// Secrets and errors follow the centered binomial distribution B(eta)
+const ETA: u32 = 2;
+
+pub struct SecurityParameters {
+    pub rank: usize,   // k
+    pub degree: usize, // n
+    pub modulo: i64,   // q
+}
+
+// Vector and matrix of ring elements
+pub type RingVector = Vec<RingElement>;
+pub type RingMatrix = Vec<RingVector>;
+
+pub struct PublicKey {
+    pub matrix: RingMatrix,
+    pub public_vector: RingVector,
+}
+
+pub struct PrivateKey {
+    pub secret_vector: RingVector,
+}
+
+#[derive(Debug)]
+pub struct Ciphertext {
+    pub preamble: RingVector,
+    pub scalars: RingElement,
+}
+
+// Uniform element of R_q
+fn sample_uniform(n: usize, q: i64, rng: &mut (impl RngCore + CryptoRng)) -> RingElement {
+    let coefficients: Vec<i64> = (0..n).map(|_| rng.gen_range(0..q)).collect();
+    RingElement::from_coefficients(&coefficients, q)
 }
 
-fn sample_ring_matrix(k: usize, q: i64) -> DMatrix<i64> {
-    DMatrix::from_fn(k, k, |_, _| thread_rng().gen_range(0..q))
+// Element of R_q with coefficients in [-eta, eta]
+fn sample_small(n: usize, q: i64, rng: &mut (impl RngCore + CryptoRng)) -> RingElement {
+    let coefficients = BinomialSampler::new(ETA).sample_vec(rng, n);
+    RingElement::from_coefficients(&coefficients, q)
 }
 
-fn modular(v: DVector<i64>, q: i64) -> DVector<i64> {
-    v.map(|x| ((x % q) + q) % q)
+fn sample_ring_vector(
+    k: usize,
+    n: usize,
+    q: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> RingVector {
+    (0..k).map(|_| sample_small(n, q, rng)).collect()
+}
+
+pub fn sample_ring_matrix(
+    k: usize,
+    n: usize,
+    q: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> RingMatrix {
+    (0..k)
+        .map(|_| (0..k).map(|_| sample_uniform(n, q, rng)).collect())
+        .collect()
+}
+
+// NTT tables for R_q when q allows it, None for the schoolbook product
+fn ntt_tables(n: usize, q: i64) -> Option<NttTables> {
+    if ntt::supports(n, q) {
+        Some(ntt::precompute(n, q))
+    } else {
+        None
+    }
 }
 
-fn modular_mat(m: DMatrix<i64>, q: i64) -> DMatrix<i64> {
-    m.map(|x| ((x % q) + q) % q)
+fn dot(a: &[RingElement], b: &[RingElement], tables: Option<&NttTables>) -> RingElement {
+    let mut result = RingElement::zero(a[0].degree(), a[0].modulo);
+    for (x, y) in a.iter().zip(b.iter()) {
+        let product = match tables {
+            Some(tables) => x.mul_ntt(y, tables),
+            None => x * y,
+        };
+        result = result + &product;
+    }
+    result
 }
 
-pub fn keygen(k: usize, q: i64) -> (DMatrix<i64>, DVector<i64>, DVector<i64>, DVector<i64>) {
-    let a = sample_ring_matrix(k, q);
-    let s0 = sample_ring_vector(k, q);
-    let e0 = sample_ring_vector(k, q);
-    let p0 = modular(&(&a * &s0) + &e0, q);
+// Inner product <a, b> in R_q
+pub fn inner_product(a: &[RingElement], b: &[RingElement]) -> RingElement {
+    let tables = ntt_tables(a[0].degree(), a[0].modulo);
+    dot(a, b, tables.as_ref())
+}
+
+// A * v, or A^T * v when transpose is set
+pub fn matrix_vector(a: &RingMatrix, v: &[RingElement], transpose: bool) -> RingVector {
+    let tables = ntt_tables(v[0].degree(), v[0].modulo);
+    (0..a.len())
+        .map(|i| {
+            let row: RingVector = if transpose {
+                a.iter().map(|column| column[i].clone()).collect()
+            } else {
+                a[i].clone()
+            };
+            dot(&row, v, tables.as_ref())
+        })
+        .collect()
+}
+
+fn add_vector(a: &[RingElement], b: &[RingElement]) -> RingVector {
+    a.iter().zip(b.iter()).map(|(x, y)| x + y).collect()
+}
+
+// Rank k module over a ring of degree n: returns (A, s, e, t = A.s + e)
+pub fn keygen(
+    k: usize,
+    n: usize,
+    q: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (RingMatrix, RingVector, RingVector, RingVector) {
+    let a = sample_ring_matrix(k, n, q, rng);
+    let s0 = sample_ring_vector(k, n, q, rng);
+    let e0 = sample_ring_vector(k, n, q, rng);
+    let p0 = add_vector(&matrix_vector(&a, &s0, false), &e0);
     (a, s0, e0, p0)
 }
 
+// Encrypt up to n message bits: u = A^T.r + e1, v = t^T.r + e2 + m.q/2
 pub fn encrypt(
-    a: &DMatrix<i64>,
-    p0: &DVector<i64>,
-    m: &DVector<i64>,
+    a: &RingMatrix,
+    p0: &RingVector,
+    m: &[i64],
     q: i64,
-) -> (DVector<i64>, DVector<i64>) {
-    let s1 = sample_ring_vector(a.nrows(), 2); // binary vector
-    let e1 = sample_ring_vector(a.ncols(), q);
-    let e = thread_rng().gen_range(0..q);
-
-    let p1 = modular((&s1.transpose() * a).transpose() + &e1, q);
-    let scalar = (&s1.transpose() * p0)[0];
-    let c = modular(
-        (m * (q / 2)) + DVector::from_element(m.len(), e) + DVector::from_element(m.len(), scalar),
-        q,
-    );
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (RingVector, RingElement) {
+    let k = a.len();
+    let n = p0[0].degree();
+    if m.len() > n {
+        panic!("Message must have at most {} bits!", n);
+    }
+
+    let s1 = sample_ring_vector(k, n, q, rng);
+    let e1 = sample_ring_vector(k, n, q, rng);
+    let e = sample_small(n, q, rng);
+
+    let p1 = add_vector(&matrix_vector(a, &s1, true), &e1);
+
+    let mut message: Vec<i64> = vec![0; n];
+    message[..m.len()].copy_from_slice(m);
+    let encoded = &RingElement::from_coefficients(&message, q) * (q / 2);
+
+    let c = inner_product(p0, &s1) + &e + &encoded;
     (p1, c)
 }
 
-pub fn decrypt(p1: &DVector<i64>, c: &DVector<i64>, s0: &DVector<i64>, q: i64) -> DVector<i64> {
-    let inner = (&p1.transpose() * s0)[0];
-    c.map(|ci| {
-        let m_approx = 2.0 * ((ci - inner) as f64) / (q as f64);
-        if m_approx.round() as i64 > 0 {
-            1
-        } else {
-            0
+// v - s^T.u = m.q/2 + small noise, coefficients near +-q/2 decode to 1
+pub fn decrypt(p1: &RingVector, c: &RingElement, s0: &RingVector, q: i64) -> Vec<i64> {
+    let noisy = c - &inner_product(p1, s0);
+    noisy
+        .centered()
+        .iter()
+        .map(|x| if x.abs() > q / 4 { 1 } else { 0 })
+        .collect()
+}
+
+// Encrypt and decrypt `trials` random bits, return the fraction that decrypts incorrectly
+pub fn failure_rate(
+    trials: usize,
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> f64 {
+    let (a, s0, _, p0) = keygen(params.rank, params.degree, params.modulo, rng);
+
+    let mut failures = 0;
+    let mut done = 0;
+    while done < trials {
+        let length = params.degree.min(trials - done);
+        let m: Vec<i64> = (0..length).map(|_| rng.gen_range(0..2)).collect();
+
+        let (p1, c) = encrypt(&a, &p0, &m, params.modulo, rng);
+        let result = decrypt(&p1, &c, &s0, params.modulo);
+
+        failures += m.iter().zip(result.iter()).filter(|(x, y)| x != y).count();
+        done += length;
+    }
+
+    failures as f64 / trials as f64
+}
+
+pub struct ModuleLwe;
+
+impl PublicKeyEncryption for ModuleLwe {
+    type Params = SecurityParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = PrivateKey;
+    type Ciphertext = Ciphertext;
+
+    fn keygen(
+        params: &SecurityParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, PrivateKey) {
+        let (a, s0, _, p0) = keygen(params.rank, params.degree, params.modulo, rng);
+        (
+            PublicKey {
+                matrix: a,
+                public_vector: p0,
+            },
+            PrivateKey { secret_vector: s0 },
+        )
+    }
+
+    fn encrypt(
+        params: &SecurityParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Ciphertext {
+        let (preamble, scalars) = encrypt(
+            &public_key.matrix,
+            &public_key.public_vector,
+            plain_text,
+            params.modulo,
+            rng,
+        );
+        Ciphertext { preamble, scalars }
+    }
+
+    fn decrypt(
+        params: &SecurityParameters,
+        secret_key: &PrivateKey,
+        cipher_text: &Ciphertext,
+    ) -> Vec<i64> {
+        decrypt(
+            &cipher_text.preamble,
+            &cipher_text.scalars,
+            &secret_key.secret_vector,
+            params.modulo,
+        )
+    }
+}
+
+impl DeterministicEncryption for ModuleLwe {
+    fn message_bits(params: &SecurityParameters) -> usize {
+        params.degree
+    }
+
+    fn ciphertext_bytes(cipher_text: &Ciphertext) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        for element in cipher_text.preamble.iter() {
+            bytes.extend(coefficients_to_bytes(&element.coefficients));
         }
-    })
+        bytes.extend(coefficients_to_bytes(&cipher_text.scalars.coefficients));
+        bytes
+    }
 }
@@ -1,6 +1,6 @@
 // Interactive Multiparty Key Exchange based on RLWE (Test Only)
-use rand::Rng;
-use rand_distr::{Distribution, Normal};
+use crate::sampler::{CdtSampler, Sampler};
+use rand::{CryptoRng, Rng, RngCore};
 use std::iter::repeat_with;
 use std::time::Instant;
 // N =   8,  Q =   97 , Duration = 1.3121ms
@@ -11,24 +11,16 @@ const N: usize = 8; // Polynomial degree (must be power of 2)
 const Q: i32 = 97; // Modulus (small prime for testing)
 const STDDEV: f64 = 0.1; // Standard deviation for noise
 
-// Sample error polynomial with clamped Gaussian noise
-fn sample_error(n: usize, q: i32, stddev: f64) -> Vec<i32> {
-    let mut rng = rand::thread_rng();
-    let normal = Normal::new(0.0, stddev).unwrap();
+// Sample error polynomial with discrete Gaussian noise
+fn sample_error(n: usize, q: i32, stddev: f64, rng: &mut (impl RngCore + CryptoRng)) -> Vec<i32> {
+    let sampler = CdtSampler::new(stddev);
     (0..n)
-        .map(|_| {
-            let mut sample = normal.sample(&mut rng).round() as i32;
-            if sample.abs() > 1 {
-                sample = sample.signum() * 1;
-            }
-            (sample + q) % q
-        })
+        .map(|_| (sampler.sample(rng) as i32 + q) % q)
         .collect()
 }
 
 // Generate non-zero secret polynomial
-fn generate_secret(n: usize, q: i32) -> Vec<i32> {
-    let mut rng = rand::thread_rng();
+fn generate_secret(n: usize, q: i32, rng: &mut (impl RngCore + CryptoRng)) -> Vec<i32> {
     (0..n).map(|_| rng.gen_range(1..q)).collect()
 }
 
@@ -54,8 +46,7 @@ fn extract_shared_key(poly: &[i32], q: i32) -> Vec<i32> {
 }
 
 // Interactive Multiparty Key Exchange
-fn multiparty_key_exchange(k: usize) {
-    let mut rng = rand::thread_rng();
+fn multiparty_key_exchange(k: usize, rng: &mut (impl RngCore + CryptoRng)) {
     let m: Vec<i32> = repeat_with(|| rng.gen_range(1..Q)).take(N).collect();
 
     let mut secrets = vec![];
@@ -64,8 +55,8 @@ fn multiparty_key_exchange(k: usize) {
 
     // Step 1: Each user selects secret s_i and sends p_i^0 to next user
     for i in 0..k {
-        let s_i = generate_secret(N, Q);
-        let e_i0 = sample_error(N, Q, STDDEV);
+        let s_i = generate_secret(N, Q, rng);
+        let e_i0 = sample_error(N, Q, STDDEV, rng);
         let p_i0: Vec<i32> = poly_mul(&m, &s_i, N, Q)
             .iter()
             .zip(e_i0.iter())
@@ -82,7 +73,7 @@ fn multiparty_key_exchange(k: usize) {
         for j in 1..(k - 1) {
             let idx = (i + j) % k;
             let s = &secrets[idx];
-            let e = sample_error(N, Q, STDDEV);
+            let e = sample_error(N, Q, STDDEV, rng);
             p = poly_mul(&p, s, N, Q)
                 .iter()
                 .zip(e.iter())
@@ -93,7 +84,7 @@ fn multiparty_key_exchange(k: usize) {
     }
 
     // Step 3: User 0 computes K0, encodes sigma and broadcasts it
-    let e_0 = sample_error(N, Q, STDDEV);
+    let e_0 = sample_error(N, Q, STDDEV, rng);
     let s_0 = &secrets[0];
     let k_0_poly: Vec<i32> = poly_mul(&intermediary_msgs[0], s_0, N, Q)
         .iter()
@@ -106,7 +97,7 @@ fn multiparty_key_exchange(k: usize) {
     println!("Shared key (sigma): {:?}", sigma);
 
     for i in 0..k {
-        let e_i = sample_error(N, Q, STDDEV);
+        let e_i = sample_error(N, Q, STDDEV, rng);
         let k_i = poly_mul(&intermediary_msgs[i], &secrets[i], N, Q)
             .iter()
             .zip(e_i.iter())
@@ -121,7 +112,7 @@ fn multiparty_key_exchange(k: usize) {
 pub fn benchmark_key_exchange(k: usize) {
     let start = Instant::now();
 
-    multiparty_key_exchange(k);
+    multiparty_key_exchange(k, &mut rand::thread_rng());
     let duration = start.elapsed();
 
     println!("N = {:>4}, Q = {:>6} -> Time: {:?}", N, Q, duration);
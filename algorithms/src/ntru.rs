@@ -1,10 +1,349 @@
-// TODO
+// NTRUEncrypt
 // q >> p
 // gcd(p, q) = 1
 // R = Z[X]/(X^N - 1)
 // F in R = [x^0,.., x^n-1]
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, RngCore};
+
+use crate::pke::PublicKeyEncryption;
 
 // Params (High Security)
 const N: i64 = 167;
 const P: i64 = 3;
 const Q: i64 = 128;
+// Number of +1/-1 coefficients in f, g and r
+const DF: i64 = 61;
+const DG: i64 = 20;
+const DR: i64 = 18;
+
+pub struct SecurityParameters {
+    pub dimension: i64,     // N
+    pub small_modulo: i64,  // p
+    pub modulo: i64,        // q (power of 2)
+    pub secret_weight: i64, // df
+    pub public_weight: i64, // dg
+    pub random_weight: i64, // dr
+}
+
+pub struct PublicKey {
+    pub polynomial: Vec<i64>, // h = p * Fq * g
+}
+
+pub struct PrivateKey {
+    pub secret_polynomial: Vec<i64>,  // f
+    pub inverse_polynomial: Vec<i64>, // Fp = f^-1 mod p
+}
+
+// Multiply polynomials in Z_modulo[X]/(X^n - 1)
+fn multiply(a: &[i64], b: &[i64], n: usize, modulo: i64) -> Vec<i64> {
+    let mut result: Vec<i64> = vec![0; n];
+    for i in 0..n {
+        if a[i] == 0 {
+            continue;
+        }
+        for j in 0..n {
+            let k = (i + j) % n;
+            result[k] = (result[k] + a[i] * b[j]).rem_euclid(modulo);
+        }
+    }
+    result
+}
+
+// Lift coefficients from [0, modulo) into (-modulo/2, modulo/2]
+fn center_lift(poly: &[i64], modulo: i64) -> Vec<i64> {
+    poly.iter()
+        .map(|&x| {
+            let x = x.rem_euclid(modulo);
+            if x > modulo / 2 {
+                x - modulo
+            } else {
+                x
+            }
+        })
+        .collect()
+}
+
+// Ternary polynomial with `ones` coefficients equal to 1 and `minus_ones` equal to -1
+fn gen_ternary_polynomial(
+    size: i64,
+    ones: i64,
+    minus_ones: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<i64> {
+    let mut poly: Vec<i64> = vec![0; size as usize];
+    for i in 0..ones as usize {
+        poly[i] = 1;
+    }
+    for i in ones as usize..(ones + minus_ones) as usize {
+        poly[i] = -1;
+    }
+
+    poly.shuffle(rng);
+    poly
+}
+
+// Drop leading zero coefficients
+fn trim(poly: &mut Vec<i64>) {
+    while poly.len() > 1 && *poly.last().unwrap() == 0 {
+        poly.pop();
+    }
+}
+
+fn degree(poly: &[i64]) -> usize {
+    poly.iter().rposition(|&x| x != 0).unwrap_or(0)
+}
+
+fn is_zero(poly: &[i64]) -> bool {
+    poly.iter().all(|&x| x == 0)
+}
+
+// a^-1 mod p for prime p (Fermat)
+fn invert_scalar(a: i64, p: i64) -> i64 {
+    let mut result = 1;
+    let mut base = a.rem_euclid(p);
+    let mut exp = p - 2;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % p;
+        }
+        base = base * base % p;
+        exp >>= 1;
+    }
+    result
+}
+
+// Schoolbook product over Z_p[X], no ring reduction
+fn multiply_plain(a: &[i64], b: &[i64], p: i64) -> Vec<i64> {
+    let mut result: Vec<i64> = vec![0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            result[i + j] = (result[i + j] + x * y).rem_euclid(p);
+        }
+    }
+    trim(&mut result);
+    result
+}
+
+fn sub_plain(a: &[i64], b: &[i64], p: i64) -> Vec<i64> {
+    let mut result: Vec<i64> = vec![0; a.len().max(b.len())];
+    for (i, elem) in result.iter_mut().enumerate() {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        *elem = (x - y).rem_euclid(p);
+    }
+    trim(&mut result);
+    result
+}
+
+// Long division over Z_p[X]: returns (quotient, remainder)
+fn divide(a: &[i64], b: &[i64], p: i64) -> (Vec<i64>, Vec<i64>) {
+    let mut remainder = a.to_vec();
+    trim(&mut remainder);
+    let deg_b = degree(b);
+    let lead_inv = invert_scalar(b[deg_b], p);
+
+    let mut quotient: Vec<i64> = vec![0; remainder.len().max(1)];
+    while !is_zero(&remainder) && degree(&remainder) >= deg_b {
+        let shift = degree(&remainder) - deg_b;
+        let coeff = remainder[degree(&remainder)] * lead_inv % p;
+        quotient[shift] = (quotient[shift] + coeff) % p;
+        for i in 0..=deg_b {
+            remainder[i + shift] = (remainder[i + shift] - coeff * b[i]).rem_euclid(p);
+        }
+        trim(&mut remainder);
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+// Inverse in Z_p[X]/(X^n - 1) for prime p using the extended Euclidean algorithm
+fn invert_mod_prime(poly: &[i64], n: usize, p: i64) -> Option<Vec<i64>> {
+    // r0 = X^n - 1
+    let mut r0: Vec<i64> = vec![0; n + 1];
+    r0[0] = p - 1;
+    r0[n] = 1;
+    let mut r1: Vec<i64> = poly.iter().map(|&x| x.rem_euclid(p)).collect();
+    trim(&mut r1);
+
+    let mut t0: Vec<i64> = vec![0];
+    let mut t1: Vec<i64> = vec![1];
+
+    while !is_zero(&r1) {
+        let (quotient, remainder) = divide(&r0, &r1, p);
+        let t2 = sub_plain(&t0, &multiply_plain(&quotient, &t1, p), p);
+        r0 = r1;
+        r1 = remainder;
+        t0 = t1;
+        t1 = t2;
+    }
+
+    // gcd(f, X^n - 1) must be a unit
+    if degree(&r0) != 0 || r0[0] == 0 {
+        return None;
+    }
+
+    let scale = invert_scalar(r0[0], p);
+    let mut inverse: Vec<i64> = vec![0; n];
+    for (i, &x) in t0.iter().enumerate() {
+        inverse[i % n] = (inverse[i % n] + x * scale).rem_euclid(p);
+    }
+    Some(inverse)
+}
+
+// Inverse in Z_q[X]/(X^n - 1) for q = 2^k: invert mod 2, then Newton lift b = b(2 - fb)
+fn invert_mod_power_of_two(poly: &[i64], n: usize, q: i64) -> Option<Vec<i64>> {
+    let mut inverse = invert_mod_prime(poly, n, 2)?;
+
+    let mut precision: i64 = 2;
+    while precision < q {
+        precision *= precision;
+        let mut correction = multiply(poly, &inverse, n, q);
+        correction.iter_mut().for_each(|x| *x = (-*x).rem_euclid(q));
+        correction[0] = (correction[0] + 2) % q;
+        inverse = multiply(&inverse, &correction, n, q);
+    }
+    Some(inverse)
+}
+
+pub fn setup() -> SecurityParameters {
+    return SecurityParameters {
+        dimension: N,
+        small_modulo: P,
+        modulo: Q,
+        secret_weight: DF,
+        public_weight: DG,
+        random_weight: DR,
+    };
+}
+
+pub fn key_gen(
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (PublicKey, PrivateKey) {
+    let n = params.dimension as usize;
+
+    // f must be invertible both mod p and mod q, resample until it is
+    let (f, f_p, f_q) = loop {
+        let f = gen_ternary_polynomial(
+            params.dimension,
+            params.secret_weight,
+            params.secret_weight - 1,
+            rng,
+        );
+        if let (Some(f_p), Some(f_q)) = (
+            invert_mod_prime(&f, n, params.small_modulo),
+            invert_mod_power_of_two(&f, n, params.modulo),
+        ) {
+            break (f, f_p, f_q);
+        }
+    };
+
+    let g = gen_ternary_polynomial(
+        params.dimension,
+        params.public_weight,
+        params.public_weight,
+        rng,
+    );
+
+    // h = p * Fq * g
+    let mut h = multiply(&f_q, &g, n, params.modulo);
+    h.iter_mut()
+        .for_each(|x| *x = (*x * params.small_modulo) % params.modulo);
+
+    return (
+        PublicKey { polynomial: h },
+        PrivateKey {
+            secret_polynomial: f,
+            inverse_polynomial: f_p,
+        },
+    );
+}
+
+// Plaintext is a ternary polynomial with coefficients in {-1, 0, 1}, at most N of them
+pub fn encrypt(
+    plain_text: &[i64],
+    public_key: &PublicKey,
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<i64> {
+    let n = params.dimension as usize;
+    if plain_text.len() > n {
+        panic!("Plaintext must have at most {} coefficients!", n);
+    }
+
+    let mut m: Vec<i64> = vec![0; n];
+    m[..plain_text.len()].copy_from_slice(plain_text);
+
+    let r = gen_ternary_polynomial(
+        params.dimension,
+        params.random_weight,
+        params.random_weight,
+        rng,
+    );
+
+    // e = r * h + m
+    let mut e = multiply(&r, &public_key.polynomial, n, params.modulo);
+    for i in 0..n {
+        e[i] = (e[i] + m[i]).rem_euclid(params.modulo);
+    }
+    e
+}
+
+pub fn decrypt(
+    cipher_text: &[i64],
+    private_key: &PrivateKey,
+    params: &SecurityParameters,
+) -> Vec<i64> {
+    let n = params.dimension as usize;
+
+    // a = f * e mod q, centered so it equals p*r*g + f*m over the integers
+    let a = center_lift(
+        &multiply(
+            &private_key.secret_polynomial,
+            cipher_text,
+            n,
+            params.modulo,
+        ),
+        params.modulo,
+    );
+
+    // m = Fp * a mod p
+    center_lift(
+        &multiply(&private_key.inverse_polynomial, &a, n, params.small_modulo),
+        params.small_modulo,
+    )
+}
+
+pub struct Ntru;
+
+impl PublicKeyEncryption for Ntru {
+    type Params = SecurityParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = PrivateKey;
+    type Ciphertext = Vec<i64>;
+
+    fn keygen(
+        params: &SecurityParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, PrivateKey) {
+        key_gen(params, rng)
+    }
+
+    fn encrypt(
+        params: &SecurityParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Vec<i64> {
+        encrypt(plain_text, public_key, params, rng)
+    }
+
+    fn decrypt(
+        params: &SecurityParameters,
+        secret_key: &PrivateKey,
+        cipher_text: &Vec<i64>,
+    ) -> Vec<i64> {
+        decrypt(cipher_text, secret_key, params)
+    }
+}
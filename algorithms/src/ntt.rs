@@ -0,0 +1,179 @@
+// Negacyclic Number Theoretic Transform over Z_q[X]/(X^n + 1)
+// n must be a power of 2 and q an odd prime. With a primitive 2n-th root of unity psi
+// (q = 1 mod 2n) the transform is complete and products are pointwise. Otherwise it stops
+// after as many layers as the roots of unity mod q allow, like Kyber with q = 3329: the
+// transform is then a list of small blocks mod X^b - gamma, multiplied as polynomials.
+// Multiplication is exact, no floating point involved.
+
+pub struct NttTables {
+    pub degree: usize, // n
+    pub modulo: i64,   // q
+    block: usize,      // b, 1 for a complete transform
+    zetas: Vec<i64>,   // psi^bitrev(i), one per butterfly group
+    gammas: Vec<i64>,  // psi^(2 bitrev(i) + 1), block i is taken mod X^b - gamma_i
+    layers_inv: i64,   // (n / b)^-1 mod q
+}
+
+fn pow_mod(base: i64, exp: i64, modulo: i64) -> i64 {
+    let mut result = 1;
+    let mut base = base.rem_euclid(modulo);
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulo;
+        }
+        base = base * base % modulo;
+        exp >>= 1;
+    }
+    result
+}
+
+fn bit_reverse(x: usize, bits: u32) -> usize {
+    if bits == 0 {
+        return 0;
+    }
+    x.reverse_bits() >> (usize::BITS - bits)
+}
+
+// Primitive 2k-th root of unity mod q: psi^k = -1 implies order exactly 2k
+fn find_root(k: usize, modulo: i64) -> i64 {
+    let exp = (modulo - 1) / (2 * k as i64);
+    for candidate in 2..modulo {
+        let psi = pow_mod(candidate, exp, modulo);
+        if pow_mod(psi, k as i64, modulo) == modulo - 1 {
+            return psi;
+        }
+    }
+    panic!("No primitive {}-th root of unity modulo {}!", 2 * k, modulo);
+}
+
+// The transform needs a power-of-two degree and an odd prime modulus. Power-of-two moduli,
+// as in LWR, have no roots of unity and keep the schoolbook product.
+pub fn supports(degree: usize, modulo: i64) -> bool {
+    degree.is_power_of_two()
+        && modulo > 2
+        && modulo % 2 == 1
+        && (3..)
+            .step_by(2)
+            .take_while(|d| d * d <= modulo)
+            .all(|d| modulo % d != 0)
+}
+
+// Precompute twiddle factors for the given ring. Call once per parameter set.
+pub fn precompute(degree: usize, modulo: i64) -> NttTables {
+    if !degree.is_power_of_two() {
+        panic!("NTT degree must be a power of 2!");
+    }
+    if modulo % 2 == 0 {
+        panic!("NTT modulus must be odd!");
+    }
+
+    // k blocks need a primitive 2k-th root of unity, 2k | q - 1
+    let two_adicity = (modulo - 1).trailing_zeros();
+    let blocks = degree.min(1 << (two_adicity - 1));
+    let psi = find_root(blocks, modulo);
+    let bits = blocks.trailing_zeros();
+    let zetas: Vec<i64> = (0..blocks)
+        .map(|i| pow_mod(psi, bit_reverse(i, bits) as i64, modulo))
+        .collect();
+    let gammas: Vec<i64> = (0..blocks)
+        .map(|i| pow_mod(psi, 2 * bit_reverse(i, bits) as i64 + 1, modulo))
+        .collect();
+
+    NttTables {
+        degree,
+        modulo,
+        block: degree / blocks,
+        zetas,
+        gammas,
+        layers_inv: pow_mod(blocks as i64, modulo - 2, modulo),
+    }
+}
+
+// In-place forward transform (Cooley-Tukey), output in bit-reversed order
+pub fn forward(poly: &mut [i64], tables: &NttTables) {
+    let q = tables.modulo;
+    poly.iter_mut().for_each(|x| *x = x.rem_euclid(q));
+
+    let mut k = 0;
+    let mut len = tables.degree / 2;
+    while len >= tables.block {
+        let mut start = 0;
+        while start < tables.degree {
+            k += 1;
+            let zeta = tables.zetas[k];
+            for j in start..start + len {
+                let t = zeta * poly[j + len] % q;
+                poly[j + len] = (poly[j] - t).rem_euclid(q);
+                poly[j] = (poly[j] + t) % q;
+            }
+            start += 2 * len;
+        }
+        len >>= 1;
+    }
+}
+
+// In-place inverse transform (Gentleman-Sande), input in bit-reversed order
+pub fn inverse(poly: &mut [i64], tables: &NttTables) {
+    let q = tables.modulo;
+
+    let mut k = tables.zetas.len();
+    let mut len = tables.block;
+    while len < tables.degree {
+        let mut start = 0;
+        while start < tables.degree {
+            k -= 1;
+            let zeta = q - tables.zetas[k];
+            for j in start..start + len {
+                let t = poly[j];
+                poly[j] = (t + poly[j + len]) % q;
+                poly[j + len] = zeta * (t - poly[j + len]).rem_euclid(q) % q;
+            }
+            start += 2 * len;
+        }
+        len <<= 1;
+    }
+
+    poly.iter_mut()
+        .for_each(|x| *x = *x * tables.layers_inv % q);
+}
+
+// a * b mod (q, X^b - gamma) for blocks of b coefficients
+fn multiply_block(a: &[i64], b: &[i64], gamma: i64, modulo: i64) -> Vec<i64> {
+    let size = a.len();
+    let mut result: Vec<i64> = vec![0; size];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            let product = x * y % modulo;
+            if i + j < size {
+                result[i + j] = (result[i + j] + product) % modulo;
+            } else {
+                // X^b = gamma
+                result[i + j - size] = (result[i + j - size] + product * gamma) % modulo;
+            }
+        }
+    }
+    result
+}
+
+// a * b in Z_q[X]/(X^n + 1), coefficients in [0, q)
+pub fn multiply(a: &[i64], b: &[i64], tables: &NttTables) -> Vec<i64> {
+    if a.len() != tables.degree || b.len() != tables.degree {
+        panic!("Polynomial lengths must match the NTT degree!");
+    }
+
+    let mut a_hat = a.to_vec();
+    let mut b_hat = b.to_vec();
+    forward(&mut a_hat, tables);
+    forward(&mut b_hat, tables);
+
+    let mut result: Vec<i64> = a_hat
+        .chunks(tables.block)
+        .zip(b_hat.chunks(tables.block))
+        .zip(tables.gammas.iter())
+        .flat_map(|((x, y), &gamma)| multiply_block(x, y, gamma, tables.modulo))
+        .collect();
+
+    inverse(&mut result, tables);
+    result
+}
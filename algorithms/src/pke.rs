@@ -0,0 +1,101 @@
+// Common interface over the public-key encryption schemes in this crate.
+// Messages are passed as vectors of small integers (bits, or ternary coefficients for NTRU),
+// each scheme converts them into its own plaintext representation.
+// All randomness comes from the caller's RNG, see rng.rs for deterministic seeding.
+use rand::{CryptoRng, RngCore};
+
+use crate::rng;
+
+pub trait PublicKeyEncryption {
+    type Params;
+    type PublicKey;
+    type SecretKey;
+    type Ciphertext;
+
+    fn keygen(
+        params: &Self::Params,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (Self::PublicKey, Self::SecretKey);
+
+    fn encrypt(
+        params: &Self::Params,
+        public_key: &Self::PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Self::Ciphertext;
+
+    fn decrypt(
+        params: &Self::Params,
+        secret_key: &Self::SecretKey,
+        cipher_text: &Self::Ciphertext,
+    ) -> Vec<i64>;
+}
+
+// Schemes whose encryption can be derandomized, as needed by the Fujisaki-Okamoto transform in fo.rs
+pub trait DeterministicEncryption: PublicKeyEncryption {
+    // Number of message bits carried by one ciphertext
+    fn message_bits(params: &Self::Params) -> usize;
+
+    // Canonical byte encoding of a ciphertext, hashed into the shared key and compared on re-encryption
+    fn ciphertext_bytes(cipher_text: &Self::Ciphertext) -> Vec<u8>;
+
+    // Encrypt with every random choice derived from the 32-byte coins
+    fn encrypt_with_coins(
+        params: &Self::Params,
+        public_key: &Self::PublicKey,
+        plain_text: &[i64],
+        coins: &[u8; 32],
+    ) -> Self::Ciphertext {
+        Self::encrypt(params, public_key, plain_text, &mut rng::chacha(*coins))
+    }
+}
+
+// Little-endian encoding of ciphertext coefficients
+pub fn coefficients_to_bytes<'a>(coefficients: impl IntoIterator<Item = &'a i64>) -> Vec<u8> {
+    coefficients
+        .into_iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect()
+}
+
+// Bytes to bits, most significant bit first
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<i64> {
+    let mut bits: Vec<i64> = Vec::with_capacity(bytes.len() * 8);
+    for byte in bytes {
+        for i in (0..8).rev() {
+            bits.push(((byte >> i) & 1) as i64);
+        }
+    }
+    bits
+}
+
+// Bits to bytes, most significant bit first. Length must be a multiple of 8
+pub fn bits_to_bytes(bits: &[i64]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(0u8, |acc, &bit| (acc << 1) | (bit & 1) as u8)
+        })
+        .collect()
+}
+
+// ISO/IEC 7816-4 padding: append 0x80, then zeros up to a multiple of block_size bytes.
+// A full block of padding is added when the message is already aligned.
+pub fn pad(message: &[u8], block_size: usize) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % block_size != 0 {
+        padded.push(0x00);
+    }
+    padded
+}
+
+// Strip the padding added by `pad`, None if it is malformed
+pub fn unpad(padded: &[u8]) -> Option<Vec<u8>> {
+    let end = padded.iter().rposition(|&byte| byte != 0x00)?;
+    if padded[end] != 0x80 {
+        return None;
+    }
+    Some(padded[..end].to_vec())
+}
@@ -1,18 +1,22 @@
 use ndarray::{Array1, Array2};
-use rand::Rng;
-use rand_distr::{Distribution, Normal};
+use rand::{CryptoRng, Rng, RngCore};
 
-// TODO: Divide input into N chunks
+use crate::pke::{
+    bits_to_bytes, bytes_to_bits, coefficients_to_bytes, pad, unpad, DeterministicEncryption,
+    PublicKeyEncryption,
+};
+use crate::sampler::{CdtSampler, Sampler};
+
+// Long inputs are encrypted in chunks of N bits, see encrypt_chunks
 
 const N: i64 = 128;
-const M: i64 = 594;
-const Q: i64 = 16411;
 
 // We'll internally handle security parameter generations as well
 pub struct SecurityParameters {
     pub dimensions: i64, // n
     pub rank: i64,       // m
     pub modulo: i64,     // q
+    pub alpha: f64,      // error width relative to q
 }
 
 pub struct PublicKey {
@@ -24,10 +28,20 @@ pub struct PrivateKey {
     pub secret_vector: Array1<i64>,
 }
 
-fn __gen_random_array2__(rows: i64, cols: i64, modulo: i64) -> Array2<i64> {
+#[derive(Debug)]
+pub struct Ciphertext {
+    pub preamble: Array2<i64>,
+    pub scalars: Array1<i64>,
+}
+
+fn __gen_random_array2__(
+    rows: i64,
+    cols: i64,
+    modulo: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Array2<i64> {
     let mut matrix = Array2::<i64>::zeros((rows as usize, cols as usize));
 
-    let mut rng = rand::thread_rng();
     for elem in matrix.iter_mut() {
         *elem = rng.gen_range(0..modulo);
     }
@@ -35,10 +49,13 @@ fn __gen_random_array2__(rows: i64, cols: i64, modulo: i64) -> Array2<i64> {
     return matrix;
 }
 
-fn __gen_random_array1__(size: i64, modulo: i64) -> Array1<i64> {
+fn __gen_random_array1__(
+    size: i64,
+    modulo: i64,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Array1<i64> {
     let mut matrix = Array1::<i64>::zeros(size as usize);
 
-    let mut rng = rand::thread_rng();
     for elem in matrix.iter_mut() {
         *elem = rng.gen_range(0..modulo);
     }
@@ -46,45 +63,95 @@ fn __gen_random_array1__(size: i64, modulo: i64) -> Array1<i64> {
     return matrix;
 }
 
-fn __error__(mean: f64, std_dev: f64, length: i64) -> Array1<i64> {
-    let mut matrix = Array1::<i64>::zeros(length as usize);
-    let normal: Normal<f64> = Normal::new(mean, std_dev).unwrap();
+// Discrete Gaussian of width s = alpha * q, i.e. standard deviation s / sqrt(2 pi)
+fn __error__(width: f64, length: i64, rng: &mut (impl RngCore + CryptoRng)) -> Array1<i64> {
+    let std_dev = width / (2.0 * std::f64::consts::PI).sqrt();
+    let sampler = CdtSampler::new(std_dev);
 
-    for elem in matrix.iter_mut() {
-        *elem = normal.sample(&mut rand::thread_rng()) as i64;
+    return Array1::from_vec(sampler.sample_vec(rng, length as usize));
+}
+
+fn is_prime(x: i64) -> bool {
+    if x < 2 {
+        return false;
+    }
+    let mut d = 2;
+    while d * d <= x {
+        if x % d == 0 {
+            return false;
+        }
+        d += 1;
     }
+    true
+}
 
-    return matrix;
+// Canonical representative of x mod q, in [0, q)
+fn reduce(x: i64, modulo: i64) -> i64 {
+    x.rem_euclid(modulo)
+}
+
+// Centered representative of x mod q, in (-q/2, q/2]
+fn center(x: i64, modulo: i64) -> i64 {
+    let x = reduce(x, modulo);
+    if x > modulo / 2 {
+        x - modulo
+    } else {
+        x
+    }
 }
 
 // Initialize security parameters and other things. Call: 1
 
 pub fn setup() -> SecurityParameters {
     // Security params for the sessions
-    let params: SecurityParameters = SecurityParameters {
-        dimensions: N, // N = Your choice
-        rank: M,       // M: 1.1 * N * LogQ
-        modulo: Q,     // Prime: N^2 < Q < 2N^2
-    };
+    return derive_params(N); // N = Your choice
+}
 
-    return params;
+// Regev's parameters as a function of n:
+// Prime: N^2 < Q < 2N^2
+// M: 1.1 * N * LogQ
+// Alpha: 1 / (sqrt(N) * Log^2 N)
+pub fn derive_params(dimensions: i64) -> SecurityParameters {
+    let mut modulo = dimensions * dimensions + 1;
+    while !is_prime(modulo) {
+        modulo += 1;
+    }
+
+    let log_q = (modulo as f64).log2();
+    let log_n = (dimensions as f64).log2();
+
+    return SecurityParameters {
+        dimensions,
+        rank: (1.1 * dimensions as f64 * log_q).ceil() as i64,
+        modulo,
+        alpha: 1.0 / ((dimensions as f64).sqrt() * log_n * log_n),
+    };
 }
 
-pub fn modify_params(_dimensions: i64, _rank: i64, _modulo: i64) -> SecurityParameters {
+pub fn modify_params(
+    _dimensions: i64,
+    _rank: i64,
+    _modulo: i64,
+    _alpha: f64,
+) -> SecurityParameters {
     return SecurityParameters {
         dimensions: _dimensions,
         rank: _rank,
         modulo: _modulo,
+        alpha: _alpha,
     };
 }
 
 // Call: 2
-pub fn key_gen(params: &SecurityParameters) -> (PublicKey, PrivateKey) {
-    let a = __gen_random_array2__(params.dimensions, params.rank, params.modulo);
+pub fn key_gen(
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (PublicKey, PrivateKey) {
+    let a = __gen_random_array2__(params.dimensions, params.rank, params.modulo, rng);
 
-    let s = __gen_random_array1__(params.dimensions, params.modulo);
+    let s = __gen_random_array1__(params.dimensions, params.modulo, rng);
 
-    let b = s.dot(&a) + __error__(0.0, 0.069, params.rank);
+    let b = s.dot(&a) + __error__(params.alpha * params.modulo as f64, params.rank, rng);
 
     // Use this to show error
     // println!("Error: {:?}", &b - s.dot(&a));
@@ -93,7 +160,7 @@ pub fn key_gen(params: &SecurityParameters) -> (PublicKey, PrivateKey) {
     return (
         PublicKey {
             matrix: a,
-            public_vector: b.mapv(|x: i64| x % params.modulo),
+            public_vector: b.mapv(|x: i64| reduce(x, params.modulo)),
         },
         PrivateKey { secret_vector: s },
     );
@@ -103,32 +170,39 @@ pub fn encrypt(
     plain_text: &Array1<i64>,
     public_key: &PublicKey,
     params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
 ) -> (Array2<i64>, Array1<i64>) {
     // random vector x
-    let x = __gen_random_array2__(params.rank, plain_text.len() as i64, 2);
+    let x = __gen_random_array2__(params.rank, plain_text.len() as i64, 2, rng);
 
-    let preamble = public_key.matrix.dot(&x).mapv(|x: i64| x % params.modulo);
+    let preamble = public_key
+        .matrix
+        .dot(&x)
+        .mapv(|x: i64| reduce(x, params.modulo));
 
-    let scalars = (public_key.public_vector.dot(&x) + ((plain_text * params.modulo) / 2))
-        .mapv(|x: i64| x % params.modulo);
+    // b.x + m * floor(q/2)
+    let scalars = (public_key.public_vector.dot(&x) + plain_text * (params.modulo / 2))
+        .mapv(|x: i64| reduce(x, params.modulo));
 
     return (preamble, scalars);
 }
 
 pub fn decrypt(
-    preabmle: Array2<i64>,
-    scalars: Array1<i64>,
+    preabmle: &Array2<i64>,
+    scalars: &Array1<i64>,
     private_key: &PrivateKey,
     params: &SecurityParameters,
 ) -> Array1<i64> {
+    // d = m * floor(q/2) + e, with a small error e
     let mut result =
-        (scalars - private_key.secret_vector.dot(&preabmle)).mapv(|x: i64| x.abs() % params.modulo);
+        (scalars - private_key.secret_vector.dot(preabmle)).mapv(|x: i64| reduce(x, params.modulo));
 
     // Use this to print result
     // println!("Result: {:?}", result);
 
+    // Round to the nearest multiple of q/2: 1 when d is closer to q/2 than to 0
     for elem in result.iter_mut() {
-        if *elem <= params.modulo / 4 {
+        if center(*elem, params.modulo).abs() <= params.modulo / 4 {
             *elem = 0;
         } else {
             *elem = 1;
@@ -137,3 +211,172 @@ pub fn decrypt(
 
     return result;
 }
+
+// The chunked mode packs n / 8 bytes into each chunk, so n must be a positive multiple of 8
+fn check_dimensions(dimensions: i64) {
+    if dimensions < 8 || dimensions % 8 != 0 {
+        panic!("Chunked encryption needs n to be a positive multiple of 8!");
+    }
+}
+
+// Encrypt a byte stream in chunks of N bits. Chunks are produced lazily, so memory stays
+// bounded by a single chunk (an n x N preamble) regardless of the input length.
+// The last chunk carries the padding, pass every chunk to decrypt_chunks.
+pub fn encrypt_chunks<'a>(
+    message: &'a [u8],
+    public_key: &'a PublicKey,
+    params: &'a SecurityParameters,
+    rng: &'a mut (impl RngCore + CryptoRng),
+) -> impl Iterator<Item = Ciphertext> + 'a {
+    check_dimensions(params.dimensions);
+    let chunk_bytes = params.dimensions as usize / 8;
+    let full_chunks = message.len() / chunk_bytes;
+
+    (0..=full_chunks).map(move |i| {
+        let chunk = if i < full_chunks {
+            message[i * chunk_bytes..(i + 1) * chunk_bytes].to_vec()
+        } else {
+            pad(&message[i * chunk_bytes..], chunk_bytes)
+        };
+
+        let (preamble, scalars) = encrypt(
+            &Array1::from_vec(bytes_to_bits(&chunk)),
+            public_key,
+            params,
+            rng,
+        );
+        Ciphertext { preamble, scalars }
+    })
+}
+
+// Decrypt a sequence of chunks produced by encrypt_chunks. None if the padding is malformed
+pub fn decrypt_chunks<I>(
+    cipher_text: I,
+    private_key: &PrivateKey,
+    params: &SecurityParameters,
+) -> Option<Vec<u8>>
+where
+    I: IntoIterator<Item = Ciphertext>,
+{
+    check_dimensions(params.dimensions);
+    let mut message: Vec<u8> = Vec::new();
+    for chunk in cipher_text {
+        let bits = decrypt(&chunk.preamble, &chunk.scalars, private_key, params);
+        message.extend(bits_to_bytes(&bits.to_vec()));
+    }
+
+    unpad(&message)
+}
+
+// Encrypt and decrypt `trials` random bits, return the fraction that decrypts incorrectly
+pub fn failure_rate(
+    trials: usize,
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> f64 {
+    let (public_key, private_key) = key_gen(params, rng);
+    let chunk = params.dimensions as usize;
+
+    let mut failures = 0;
+    let mut done = 0;
+    while done < trials {
+        let length = chunk.min(trials - done);
+        let plain_text = __gen_random_array1__(length as i64, 2, rng);
+
+        let (preamble, scalars) = encrypt(&plain_text, &public_key, params, rng);
+        let result = decrypt(&preamble, &scalars, &private_key, params);
+
+        failures += plain_text
+            .iter()
+            .zip(result.iter())
+            .filter(|(x, y)| x != y)
+            .count();
+        done += length;
+    }
+
+    failures as f64 / trials as f64
+}
+
+// Decryption noise of `trials` random bits, in units of q. Decryption fails beyond q/4
+pub fn decryption_noise(
+    trials: usize,
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<i64> {
+    let (public_key, private_key) = key_gen(params, rng);
+    let chunk = params.dimensions as usize;
+
+    let mut noise: Vec<i64> = Vec::with_capacity(trials);
+    while noise.len() < trials {
+        let length = chunk.min(trials - noise.len());
+        let plain_text = __gen_random_array1__(length as i64, 2, rng);
+
+        let (preamble, scalars) = encrypt(&plain_text, &public_key, params, rng);
+        let noisy = scalars - private_key.secret_vector.dot(&preamble);
+
+        noise.extend(
+            noisy
+                .iter()
+                .zip(plain_text.iter())
+                .map(|(x, m)| center(x - m * (params.modulo / 2), params.modulo)),
+        );
+    }
+    noise
+}
+
+pub struct Regev;
+
+impl PublicKeyEncryption for Regev {
+    type Params = SecurityParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = PrivateKey;
+    type Ciphertext = Ciphertext;
+
+    fn keygen(
+        params: &SecurityParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, PrivateKey) {
+        key_gen(params, rng)
+    }
+
+    fn encrypt(
+        params: &SecurityParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Ciphertext {
+        let (preamble, scalars) = encrypt(
+            &Array1::from_vec(plain_text.to_vec()),
+            public_key,
+            params,
+            rng,
+        );
+        Ciphertext { preamble, scalars }
+    }
+
+    fn decrypt(
+        params: &SecurityParameters,
+        secret_key: &PrivateKey,
+        cipher_text: &Ciphertext,
+    ) -> Vec<i64> {
+        decrypt(
+            &cipher_text.preamble,
+            &cipher_text.scalars,
+            secret_key,
+            params,
+        )
+        .to_vec()
+    }
+}
+
+impl DeterministicEncryption for Regev {
+    fn message_bits(params: &SecurityParameters) -> usize {
+        params.dimensions as usize
+    }
+
+    fn ciphertext_bytes(cipher_text: &Ciphertext) -> Vec<u8> {
+        let mut bytes = coefficients_to_bytes(cipher_text.preamble.iter());
+        bytes.extend(coefficients_to_bytes(cipher_text.scalars.iter()));
+        bytes
+    }
+}
@@ -1,11 +1,16 @@
+use std::ops::{Add, Mul, Neg, Sub};
 use std::vec;
 
-use rustfft::num_traits::Zero;
-use rustfft::{num_complex::Complex, FftPlanner};
+use rand::{CryptoRng, Rng, RngCore};
 
-use rand::Rng;
-use rand_distr::{Distribution, Normal};
+use crate::ntt::{self, NttTables};
+use crate::pke::{
+    bits_to_bytes, bytes_to_bits, coefficients_to_bytes, pad, unpad, DeterministicEncryption,
+    PublicKeyEncryption,
+};
+use crate::sampler::{CdtSampler, Sampler};
 
+// 3329 only has 256-th roots of unity, so the NTT of Z_Q[X]/(X^N + 1) stops at blocks of 4
 const N: i64 = 512;
 const Q: i64 = 3329;
 const STD_DEV: f64 = 1.0;
@@ -13,100 +18,216 @@ const STD_DEV: f64 = 1.0;
 pub struct SecurityParameters {
     pub dimension: i64,
     pub modulo: i64,
+    pub ntt: NttTables,
 }
 
 pub struct PrivateKey {
-    pub secret_vector: Vec<i64>,
+    pub secret_vector: RingElement,
 }
 
 pub struct PublicKey {
-    pub polynomial: Vec<i64>,
-    pub error_polynomial: Vec<i64>,
+    pub polynomial: RingElement,
+    pub error_polynomial: RingElement,
 }
 
-// Add polynomials
-fn add(a: &[i64], b: &[i64]) -> Vec<i64> {
-    let mut result: Vec<i64> = vec![0; a.len()];
-    if a.len() != b.len() {
-        panic!("Vector lengths must be equal for addition!");
+#[derive(Debug)]
+pub struct Ciphertext {
+    pub preamble: RingElement,
+    pub scalars: RingElement,
+}
+
+// Element of R_q = Z_q[X]/(X^n + 1), coefficients kept canonical in [0, q)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingElement {
+    pub coefficients: Vec<i64>,
+    pub modulo: i64,
+}
+
+impl RingElement {
+    pub fn zero(degree: usize, modulo: i64) -> RingElement {
+        RingElement {
+            coefficients: vec![0; degree],
+            modulo,
+        }
+    }
+
+    // Coefficients of any sign, reduced into [0, q)
+    pub fn from_coefficients(coefficients: &[i64], modulo: i64) -> RingElement {
+        RingElement {
+            coefficients: canonical(coefficients, modulo),
+            modulo,
+        }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    // Representatives in (-q/2, q/2]
+    pub fn centered(&self) -> Vec<i64> {
+        centered(&self.coefficients, self.modulo)
+    }
+
+    // Product through the NTT, the tables must match this ring
+    pub fn mul_ntt(&self, other: &RingElement, tables: &NttTables) -> RingElement {
+        if tables.degree != self.degree() || tables.modulo != self.modulo {
+            panic!("NTT tables do not match the ring!");
+        }
+        RingElement {
+            coefficients: ntt::multiply(&self.coefficients, &other.coefficients, tables),
+            modulo: self.modulo,
+        }
     }
 
-    for iter in 0..a.len() {
-        result[iter] = a[iter] + b[iter];
+    fn check_compatible(&self, other: &RingElement) {
+        if self.degree() != other.degree() || self.modulo != other.modulo {
+            panic!("Ring elements must belong to the same ring!");
+        }
     }
-    result
 }
 
-// Subtract polynomials
-fn sub(a: &[i64], b: &[i64]) -> Vec<i64> {
-    let mut result: Vec<i64> = vec![0; a.len()];
-    if a.len() != b.len() {
-        panic!("Vector lengths must be equal for subtraction!");
+// Reduce coefficients into [0, q)
+pub fn canonical(poly: &[i64], modulo: i64) -> Vec<i64> {
+    poly.iter().map(|x| x.rem_euclid(modulo)).collect()
+}
+
+// Reduce coefficients into (-q/2, q/2]
+pub fn centered(poly: &[i64], modulo: i64) -> Vec<i64> {
+    poly.iter()
+        .map(|x| {
+            let x = x.rem_euclid(modulo);
+            if x > modulo / 2 {
+                x - modulo
+            } else {
+                x
+            }
+        })
+        .collect()
+}
+
+// Reduce a polynomial of any length by X^n + 1, using X^n = -1
+pub fn reduce(poly: &[i64], degree: usize, modulo: i64) -> RingElement {
+    let mut result: Vec<i64> = vec![0; degree];
+    for (i, &coeff) in poly.iter().enumerate() {
+        if (i / degree) % 2 == 0 {
+            result[i % degree] = (result[i % degree] + coeff).rem_euclid(modulo);
+        } else {
+            result[i % degree] = (result[i % degree] - coeff).rem_euclid(modulo);
+        }
+    }
+    RingElement {
+        coefficients: result,
+        modulo,
     }
+}
 
-    for iter in 0..a.len() {
-        result[iter] = a[iter] - b[iter];
+// Schoolbook product followed by negacyclic reduction, O(n^2) reference for the NTT
+pub fn schoolbook_multiply(a: &RingElement, b: &RingElement) -> RingElement {
+    a.check_compatible(b);
+
+    let mut product: Vec<i64> = vec![0; 2 * a.degree() - 1];
+    for (i, &x) in a.coefficients.iter().enumerate() {
+        for (j, &y) in b.coefficients.iter().enumerate() {
+            product[i + j] = (product[i + j] + x * y) % a.modulo;
+        }
     }
-    result
+    reduce(&product, a.degree(), a.modulo)
 }
 
-// Reduce polynomial by X^n + 1
-fn reduce(poly: &[i64], degree: i64, modulo: i64) -> Vec<i64> {
-    let mut result: Vec<i64> = vec![0; degree as usize];
-    for i in degree..poly.len() as i64 {
-        let wrap = (i - degree) as usize;
-        result[wrap] = (poly[wrap] - poly[i as usize]) % modulo;
+impl Add<&RingElement> for &RingElement {
+    type Output = RingElement;
+
+    fn add(self, other: &RingElement) -> RingElement {
+        self.check_compatible(other);
+        RingElement {
+            coefficients: self
+                .coefficients
+                .iter()
+                .zip(other.coefficients.iter())
+                .map(|(x, y)| (x + y) % self.modulo)
+                .collect(),
+            modulo: self.modulo,
+        }
     }
+}
 
-    result
+impl Sub<&RingElement> for &RingElement {
+    type Output = RingElement;
+
+    fn sub(self, other: &RingElement) -> RingElement {
+        self.check_compatible(other);
+        RingElement {
+            coefficients: self
+                .coefficients
+                .iter()
+                .zip(other.coefficients.iter())
+                .map(|(x, y)| (x - y).rem_euclid(self.modulo))
+                .collect(),
+            modulo: self.modulo,
+        }
+    }
 }
 
-// Multiply polynomials using FFT
-fn multiply(a: &[i64], b: &[i64]) -> Vec<i64> {
-    // Determine the size for zero-padding (next power of 2)
-    let n = (a.len() + b.len() - 1).next_power_of_two();
+impl Mul<&RingElement> for &RingElement {
+    type Output = RingElement;
 
-    // Zero-pad the input arrays
-    let mut a_padded: Vec<Complex<f64>> = vec![Complex::zero(); n];
-    let mut b_padded: Vec<Complex<f64>> = vec![Complex::zero(); n];
-    for (i, &coeff) in a.iter().enumerate() {
-        a_padded[i] = Complex::new(coeff as f64, 0.0);
+    fn mul(self, other: &RingElement) -> RingElement {
+        schoolbook_multiply(self, other)
     }
-    for (i, &coeff) in b.iter().enumerate() {
-        b_padded[i] = Complex::new(coeff as f64, 0.0);
+}
+
+// Scalar multiplication
+impl Mul<i64> for &RingElement {
+    type Output = RingElement;
+
+    fn mul(self, scalar: i64) -> RingElement {
+        let scalar = scalar.rem_euclid(self.modulo);
+        RingElement {
+            coefficients: self
+                .coefficients
+                .iter()
+                .map(|x| x * scalar % self.modulo)
+                .collect(),
+            modulo: self.modulo,
+        }
     }
+}
 
-    // Create the FFT planner
-    let mut planner = FftPlanner::new();
-    let fft = planner.plan_fft_forward(n);
-    let ifft = planner.plan_fft_inverse(n);
+impl Neg for &RingElement {
+    type Output = RingElement;
+
+    fn neg(self) -> RingElement {
+        RingElement {
+            coefficients: self
+                .coefficients
+                .iter()
+                .map(|x| (self.modulo - x) % self.modulo)
+                .collect(),
+            modulo: self.modulo,
+        }
+    }
+}
 
-    // Perform FFT on both padded polynomials
-    fft.process(&mut a_padded);
-    fft.process(&mut b_padded);
+impl Add<&RingElement> for RingElement {
+    type Output = RingElement;
 
-    // Pointwise multiply the FFT results
-    let mut result_fft: Vec<Complex<f64>> = a_padded
-        .iter()
-        .zip(b_padded.iter())
-        .map(|(a, b)| a * b)
-        .collect();
+    fn add(self, other: &RingElement) -> RingElement {
+        &self + other
+    }
+}
 
-    // Perform the inverse FFT
-    ifft.process(&mut result_fft);
+impl Sub<&RingElement> for RingElement {
+    type Output = RingElement;
 
-    // Normalize and round the results to integers (scale by 1/n due to IFFT)
-    result_fft
-        .iter()
-        .map(|x| (x.re / n as f64).round() as i64)
-        .collect()
+    fn sub(self, other: &RingElement) -> RingElement {
+        &self - other
+    }
 }
 
 // Sample a small polynomial
-fn gen_small_polynomial(size: i64) -> Vec<i64> {
+fn gen_small_polynomial(size: i64, rng: &mut (impl RngCore + CryptoRng)) -> Vec<i64> {
     let mut matrix: Vec<i64> = vec![0; size as usize];
 
-    let mut rng = rand::thread_rng();
     for elem in matrix.iter_mut() {
         *elem = rng.gen_range(-1..=1);
     }
@@ -114,37 +235,34 @@ fn gen_small_polynomial(size: i64) -> Vec<i64> {
     return matrix;
 }
 
-fn error(mean: f64, std_dev: f64, length: i64) -> Vec<i64> {
-    let mut matrix: Vec<i64> = vec![0; length as usize];
-    let normal: Normal<f64> = Normal::new(mean, std_dev).unwrap();
-
-    for elem in matrix.iter_mut() {
-        *elem = normal.sample(&mut rand::thread_rng()) as i64;
-    }
-
-    return matrix;
+// Discrete Gaussian error polynomial
+fn error(std_dev: f64, length: i64, rng: &mut (impl RngCore + CryptoRng)) -> Vec<i64> {
+    CdtSampler::new(std_dev).sample_vec(rng, length as usize)
 }
 
 pub fn setup() -> SecurityParameters {
     return SecurityParameters {
         dimension: N,
         modulo: Q,
+        ntt: ntt::precompute(N as usize, Q),
     };
 }
 
-pub fn key_gen(params: &SecurityParameters) -> (PublicKey, PrivateKey) {
+pub fn key_gen(
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (PublicKey, PrivateKey) {
+    let q = params.modulo;
+
     // Secret vector
-    let secret: Vec<i64> = gen_small_polynomial(params.dimension);
+    let secret = RingElement::from_coefficients(&gen_small_polynomial(params.dimension, rng), q);
 
     // Random polynomial
-    let poly: Vec<i64> = gen_small_polynomial(params.dimension);
+    let poly = RingElement::from_coefficients(&gen_small_polynomial(params.dimension, rng), q);
 
-    // B
-    // a.s
-    let mul_result: Vec<i64> = reduce(&multiply(&poly, &secret), params.dimension, params.modulo);
-    // a.s + e1
-    let mut error_poly: Vec<i64> = add(&mul_result, &error(0.0, STD_DEV, params.dimension));
-    error_poly.iter_mut().for_each(|x| *x %= params.modulo);
+    // B = a.s + e1
+    let error_poly = poly.mul_ntt(&secret, &params.ntt)
+        + &RingElement::from_coefficients(&error(STD_DEV, params.dimension, rng), q);
 
     return (
         PublicKey {
@@ -157,71 +275,142 @@ pub fn key_gen(params: &SecurityParameters) -> (PublicKey, PrivateKey) {
     );
 }
 
-// TODO: Complete this
-fn encrypt_chunk() {}
+// Encrypt one block of exactly N bits under fresh randomness
+fn encrypt_chunk(
+    block: &[i64],
+    params: &SecurityParameters,
+    key: &PublicKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Ciphertext {
+    let q = params.modulo;
+
+    let error_1 = RingElement::from_coefficients(&error(STD_DEV, params.dimension, rng), q);
+    let error_2 = RingElement::from_coefficients(&error(STD_DEV, params.dimension, rng), q);
+    let r = RingElement::from_coefficients(&error(STD_DEV, params.dimension, rng), q);
 
+    // preamble = a.r + e1
+    let preamble = key.polynomial.mul_ntt(&r, &params.ntt) + &error_1;
+
+    // scalars = b.r + e2 + m.q/2
+    let message = &RingElement::from_coefficients(block, q) * (q / 2);
+    let scalars = key.error_polynomial.mul_ntt(&r, &params.ntt) + &error_2 + &message;
+
+    Ciphertext { preamble, scalars }
+}
+
+// Encrypt up to N bits, shorter plaintexts are padded with zero coefficients
 pub fn encrypt(
     plaintext: &[i64],
     params: &SecurityParameters,
     key: &PublicKey,
-) -> (Vec<i64>, Vec<i64>) {
-    let error_1 = error(0.0, STD_DEV, params.dimension);
-    let error_2 = error(0.0, STD_DEV, params.dimension);
-    let r = error(0.0, STD_DEV, params.dimension);
-
-    // preamble
-    let preamble = add(
-        &reduce(
-            &multiply(&key.polynomial, &r),
-            params.dimension,
-            params.modulo,
-        ),
-        &error_1,
-    );
-
-    // scalars
-    let prepend = add(
-        &reduce(
-            &multiply(&key.error_polynomial, &r),
-            params.dimension,
-            params.modulo,
-        ),
-        &error_2,
-    );
-    let mut append = vec![0; params.dimension as usize];
-    for i in 0..params.dimension {
-        append[i as usize] = plaintext[i as usize] * (params.modulo / 2);
+    rng: &mut (impl RngCore + CryptoRng),
+) -> (RingElement, RingElement) {
+    let dimension = params.dimension as usize;
+    if plaintext.len() > dimension {
+        panic!(
+            "Plaintext must have at most {} bits, use encrypt_message!",
+            dimension
+        );
     }
 
-    let mut scalars = add(&prepend, &append);
-    scalars.iter_mut().for_each(|x| *x %= params.modulo);
+    let mut block: Vec<i64> = vec![0; dimension];
+    block[..plaintext.len()].copy_from_slice(plaintext);
+
+    let cipher_text = encrypt_chunk(&block, params, key, rng);
+    return (cipher_text.preamble, cipher_text.scalars);
+}
+
+// Encrypt a message of any length: pad it, split it into N-bit blocks and encrypt each block
+pub fn encrypt_message(
+    message: &[u8],
+    params: &SecurityParameters,
+    key: &PublicKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<Ciphertext> {
+    let block_bytes = params.dimension as usize / 8;
+
+    bytes_to_bits(&pad(message, block_bytes))
+        .chunks(params.dimension as usize)
+        .map(|block| encrypt_chunk(block, params, key, rng))
+        .collect()
+}
+
+// Decrypt every block, reassemble and strip the padding. None if the padding is malformed
+pub fn decrypt_message(
+    cipher_text: &[Ciphertext],
+    params: &SecurityParameters,
+    key: &PrivateKey,
+) -> Option<Vec<u8>> {
+    let mut bits: Vec<i64> = Vec::with_capacity(cipher_text.len() * params.dimension as usize);
+    for block in cipher_text {
+        bits.extend(decrypt(&block.preamble, &block.scalars, params, key));
+    }
 
-    return (preamble, scalars);
+    unpad(&bits_to_bytes(&bits))
 }
 
 pub fn decrypt(
-    preamble: &Vec<i64>,
-    scalars: &Vec<i64>,
+    preamble: &RingElement,
+    scalars: &RingElement,
     params: &SecurityParameters,
     key: &PrivateKey,
 ) -> Vec<i64> {
-    let mut r: Vec<i64> = sub(
-        &scalars,
-        &reduce(
-            &multiply(&preamble, &key.secret_vector),
-            params.dimension,
-            params.modulo,
-        ),
-    );
-    r.iter_mut().for_each(|x| *x %= params.modulo);
+    let r = scalars - &preamble.mul_ntt(&key.secret_vector, &params.ntt);
 
-    let mut result: Vec<i64> = vec![0; params.dimension as usize];
-    for i in 0..params.dimension {
-        if r[i as usize] < params.modulo / 4 {
-            result[i as usize] = 0;
-        } else {
-            result[i as usize] = 1;
-        }
+    // Centered coefficients close to +-q/2 decode to 1, those close to 0 decode to 0
+    r.centered()
+        .iter()
+        .map(|x| if x.abs() > params.modulo / 4 { 1 } else { 0 })
+        .collect()
+}
+
+pub struct RingLwe;
+
+impl PublicKeyEncryption for RingLwe {
+    type Params = SecurityParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = PrivateKey;
+    type Ciphertext = Ciphertext;
+
+    fn keygen(
+        params: &SecurityParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, PrivateKey) {
+        key_gen(params, rng)
+    }
+
+    fn encrypt(
+        params: &SecurityParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Ciphertext {
+        let (preamble, scalars) = encrypt(plain_text, params, public_key, rng);
+        Ciphertext { preamble, scalars }
+    }
+
+    fn decrypt(
+        params: &SecurityParameters,
+        secret_key: &PrivateKey,
+        cipher_text: &Ciphertext,
+    ) -> Vec<i64> {
+        decrypt(
+            &cipher_text.preamble,
+            &cipher_text.scalars,
+            params,
+            secret_key,
+        )
+    }
+}
+
+impl DeterministicEncryption for RingLwe {
+    fn message_bits(params: &SecurityParameters) -> usize {
+        params.dimension as usize
+    }
+
+    fn ciphertext_bytes(cipher_text: &Ciphertext) -> Vec<u8> {
+        let mut bytes = coefficients_to_bytes(&cipher_text.preamble.coefficients);
+        bytes.extend(coefficients_to_bytes(&cipher_text.scalars.coefficients));
+        bytes
     }
-    result
 }
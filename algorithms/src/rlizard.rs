@@ -0,0 +1,264 @@
+// RLizard, the ring variant of Lizard over R_q = Z_q[X]/(X^n + 1).
+// Keys are a pair of ring elements instead of m x (n + l) matrices. Ring products use the
+// arithmetic of ringlwe.rs and ciphertexts are rounded from q to p with the same add-and-mask
+// trick as lizard.rs. q, p and t are powers of two, so every reduction is a mask.
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::lizard::sampler_d2;
+use crate::pke::{DeterministicEncryption, PublicKeyEncryption};
+use crate::ringlwe::RingElement;
+use crate::sampler::{CdtSampler, Sampler};
+
+// Neither set is from the RLizard paper: n, q, p, t, h_s and h_r are ours, picked next to the
+// Lizard reference set, and no security level is claimed for them. Both use the Lizard reference
+// noise table.
+pub struct SecurityParameters {
+    pub name: &'static str,
+    pub degree: usize, // n, also the number of message symbols
+    pub log_q: u32,
+    pub log_p: u32,
+    pub log_t: u32,
+    pub hs: usize, // Hamming weight of the secret s
+    pub hr: usize, // Hamming weight of the ephemeral r
+    pub noise: CdtSampler,
+}
+
+pub fn custom_n1024() -> SecurityParameters {
+    SecurityParameters {
+        name: "CUSTOM_N1024",
+        degree: 1024,
+        log_q: 11,
+        log_p: 8,
+        log_t: 1,
+        hs: 128,
+        hr: 128,
+        noise: sampler_d2(),
+    }
+}
+
+pub fn custom_n2048() -> SecurityParameters {
+    SecurityParameters {
+        name: "CUSTOM_N2048",
+        degree: 2048,
+        log_q: 12,
+        log_p: 9,
+        log_t: 1,
+        hs: 256,
+        hr: 256,
+        noise: sampler_d2(),
+    }
+}
+
+pub fn setup() -> SecurityParameters {
+    custom_n1024()
+}
+
+pub fn parameter_sets() -> Vec<SecurityParameters> {
+    vec![custom_n1024(), custom_n2048()]
+}
+
+// Rounding constants as in lizard.rs, here for coefficients in [0, q) rather than in a u16
+impl SecurityParameters {
+    fn modulo(&self) -> i64 {
+        1 << self.log_q
+    }
+
+    fn rd_add(&self) -> i64 {
+        1 << (self.log_q - self.log_p - 1)
+    }
+
+    fn rd_and(&self) -> i64 {
+        !((1 << (self.log_q - self.log_p)) - 1)
+    }
+
+    fn dec_add(&self) -> i64 {
+        1 << (self.log_q - self.log_t - 1)
+    }
+}
+
+// Public key sizes in bytes, a and b at log_q bits per coefficient
+pub fn public_key_size(params: &SecurityParameters) -> usize {
+    2 * params.degree * params.log_q as usize / 8
+}
+
+// Ciphertext size in bytes, both components rounded to log_p bits
+pub fn ciphertext_size(params: &SecurityParameters) -> usize {
+    2 * params.degree * params.log_p as usize / 8
+}
+
+type SecretKey = RingElement;
+
+pub struct PublicKey {
+    pub a: RingElement,
+    pub b: RingElement,
+}
+
+#[derive(Debug)]
+pub struct Ciphertext {
+    pub a: RingElement,
+    pub b: RingElement,
+}
+
+// Ternary polynomial with exactly `weight` nonzero coefficients
+fn sparse_polynomial(
+    params: &SecurityParameters,
+    weight: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> RingElement {
+    let mut coefficients: Vec<i64> = vec![0; params.degree];
+    let mut placed = 0;
+    while placed < weight {
+        let index = rng.gen_range(0..params.degree);
+        if coefficients[index] == 0 {
+            coefficients[index] = if rng.gen::<bool>() { 1 } else { -1 };
+            placed += 1;
+        }
+    }
+    RingElement::from_coefficients(&coefficients, params.modulo())
+}
+
+// Round every coefficient to the nearest multiple of q/p
+fn round(poly: &RingElement, params: &SecurityParameters) -> RingElement {
+    let (rd_add, rd_and, mask) = (params.rd_add(), params.rd_and(), params.modulo() - 1);
+    RingElement {
+        coefficients: poly
+            .coefficients
+            .iter()
+            .map(|x| (x + rd_add) & rd_and & mask)
+            .collect(),
+        modulo: poly.modulo,
+    }
+}
+
+pub fn gen_sk(params: &SecurityParameters, rng: &mut (impl RngCore + CryptoRng)) -> SecretKey {
+    sparse_polynomial(params, params.hs, rng)
+}
+
+// b = a.s + e
+pub fn gen_pk(
+    params: &SecurityParameters,
+    sk: &SecretKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> PublicKey {
+    let q = params.modulo();
+    let a_coefficients: Vec<i64> = (0..params.degree).map(|_| rng.gen_range(0..q)).collect();
+    let a = RingElement::from_coefficients(&a_coefficients, q);
+    let e = RingElement::from_coefficients(&params.noise.sample_vec(rng, params.degree), q);
+
+    let b = &a * sk + &e;
+    PublicKey { a, b }
+}
+
+// Encrypt n symbols of Z_t: c = (a.r, b.r + m * q/t) rounded to p, r sparse and ternary
+pub fn encrypt(
+    params: &SecurityParameters,
+    pk: &PublicKey,
+    plaintext: &[i64],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Ciphertext {
+    if plaintext.len() > params.degree {
+        panic!("Plaintext must have at most {} symbols!", params.degree);
+    }
+    if plaintext.iter().any(|&x| x >> params.log_t != 0) {
+        panic!("Plaintext symbols must be below t = {}!", 1 << params.log_t);
+    }
+
+    let q = params.modulo();
+    let mut message: Vec<i64> = vec![0; params.degree];
+    message[..plaintext.len()].copy_from_slice(plaintext);
+    let encoded = &RingElement::from_coefficients(&message, q) * (q >> params.log_t);
+
+    let r = sparse_polynomial(params, params.hr, rng);
+    Ciphertext {
+        a: round(&(&pk.a * &r), params),
+        b: round(&(&pk.b * &r + &encoded), params),
+    }
+}
+
+// b - a.s = m * q/t + small noise, rounded to the top log_t bits
+pub fn decrypt(params: &SecurityParameters, sk: &SecretKey, ctx: &Ciphertext) -> Vec<i64> {
+    let noisy = &ctx.b - &(&ctx.a * sk);
+    let (dec_add, mask) = (params.dec_add(), params.modulo() - 1);
+    noisy
+        .coefficients
+        .iter()
+        .map(|x| ((x + dec_add) & mask) >> (params.log_q - params.log_t))
+        .collect()
+}
+
+// Encrypt and decrypt `trials` random symbols, return the fraction that decrypts incorrectly
+pub fn failure_rate(
+    trials: usize,
+    params: &SecurityParameters,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> f64 {
+    let sk = gen_sk(params, rng);
+    let pk = gen_pk(params, &sk, rng);
+
+    let mut failures = 0;
+    let mut done = 0;
+    while done < trials {
+        let length = params.degree.min(trials - done);
+        let plaintext: Vec<i64> = (0..length)
+            .map(|_| rng.gen_range(0..1 << params.log_t))
+            .collect();
+
+        let result = decrypt(params, &sk, &encrypt(params, &pk, &plaintext, rng));
+
+        failures += (0..length).filter(|&i| plaintext[i] != result[i]).count();
+        done += length;
+    }
+
+    failures as f64 / trials as f64
+}
+
+pub struct RLizard;
+
+impl PublicKeyEncryption for RLizard {
+    type Params = SecurityParameters;
+    type PublicKey = PublicKey;
+    type SecretKey = SecretKey;
+    type Ciphertext = Ciphertext;
+
+    fn keygen(
+        params: &SecurityParameters,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (PublicKey, SecretKey) {
+        let sk = gen_sk(params, rng);
+        let pk = gen_pk(params, &sk, rng);
+        (pk, sk)
+    }
+
+    fn encrypt(
+        params: &SecurityParameters,
+        public_key: &PublicKey,
+        plain_text: &[i64],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Ciphertext {
+        encrypt(params, public_key, plain_text, rng)
+    }
+
+    fn decrypt(
+        params: &SecurityParameters,
+        secret_key: &SecretKey,
+        cipher_text: &Ciphertext,
+    ) -> Vec<i64> {
+        decrypt(params, secret_key, cipher_text)
+    }
+}
+
+impl DeterministicEncryption for RLizard {
+    fn message_bits(params: &SecurityParameters) -> usize {
+        params.degree
+    }
+
+    fn ciphertext_bytes(cipher_text: &Ciphertext) -> Vec<u8> {
+        cipher_text
+            .a
+            .coefficients
+            .iter()
+            .chain(cipher_text.b.coefficients.iter())
+            .flat_map(|&x| (x as u16).to_le_bytes())
+            .collect()
+    }
+}
@@ -0,0 +1,61 @@
+// Deterministic randomness for reproducible runs and known-answer tests.
+// Feeding a scheme the same seed reproduces its keys and ciphertexts byte for byte.
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Shake256, Shake256Reader};
+
+// ChaCha20 keystream from a 32-byte seed
+pub fn chacha(seed: [u8; 32]) -> ChaCha20Rng {
+    ChaCha20Rng::from_seed(seed)
+}
+
+// SHAKE-256 expansion of a seed of any length
+pub struct ShakeRng {
+    reader: Shake256Reader,
+}
+
+impl ShakeRng {
+    pub fn new(seed: &[u8]) -> ShakeRng {
+        let mut hasher = Shake256::default();
+        hasher.update(seed);
+        ShakeRng {
+            reader: hasher.finalize_xof(),
+        }
+    }
+}
+
+impl RngCore for ShakeRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.reader.read(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.reader.read(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.reader.read(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ShakeRng {}
+
+// Short SHAKE-256 digest, in hex, used to pin known-answer outputs
+pub fn fingerprint(bytes: &[u8]) -> String {
+    let mut hasher = Shake256::default();
+    hasher.update(bytes);
+    let mut digest = [0u8; 16];
+    hasher.finalize_xof().read(&mut digest);
+
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,268 @@
+// Discrete Gaussian and centered binomial samplers shared by every scheme.
+// All samplers take a caller-supplied RNG and run in constant time: every sample reads the
+// same amount of randomness and scans the whole table, without data dependent branches.
+use rand::RngCore;
+
+// Tail cut of the discrete Gaussian, in multiples of sigma
+const TAIL_CUT: f64 = 13.0;
+// Precision of the CDT entries, in bits
+const CDT_BITS: u32 = 63;
+
+pub trait Sampler {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> i64;
+
+    // Probability of drawing x
+    fn probability(&self, x: i64) -> f64;
+
+    fn sample_vec<R: RngCore + ?Sized>(&self, rng: &mut R, length: usize) -> Vec<i64> {
+        (0..length).map(|_| self.sample(rng)).collect()
+    }
+}
+
+// 1 if a < b, 0 otherwise. a and b must be below 2^63
+fn ct_lt(a: u64, b: u64) -> u64 {
+    a.wrapping_sub(b) >> 63
+}
+
+// 1 if a == b, 0 otherwise
+fn ct_eq(a: i64, b: i64) -> i64 {
+    let diff = (a ^ b) as u64;
+    (1 ^ ((diff | diff.wrapping_neg()) >> 63)) as i64
+}
+
+// Apply a random sign to a non-negative sample: x or -x
+fn ct_sign(magnitude: i64, sign: i64) -> i64 {
+    (magnitude ^ -sign) + sign
+}
+
+// Distribution of |x| for a discrete Gaussian of standard deviation sigma: P(0) = rho(0) / S and
+// P(k) = 2 rho(k) / S, so that a uniform sign afterwards gives the symmetric distribution
+fn folded_gaussian(sigma: f64) -> Vec<f64> {
+    let tail = (TAIL_CUT * sigma).ceil().max(1.0) as i64;
+    let rho: Vec<f64> = (0..=tail)
+        .map(|k| (-((k * k) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f64 = rho[0] + 2.0 * rho[1..].iter().sum::<f64>();
+
+    rho.iter()
+        .enumerate()
+        .map(|(k, p)| if k == 0 { p / total } else { 2.0 * p / total })
+        .collect()
+}
+
+// Cumulative distribution table sampler
+pub struct CdtSampler {
+    // table[k] = largest random value (out of 2^bits) mapped to |x| <= k
+    pub table: Vec<u64>,
+    pub bits: u32,
+}
+
+impl CdtSampler {
+    pub fn new(sigma: f64) -> CdtSampler {
+        CdtSampler::with_precision(sigma, CDT_BITS)
+    }
+
+    // Table with entries of the given bit length, e.g. to match a scheme's published tables
+    pub fn with_precision(sigma: f64, bits: u32) -> CdtSampler {
+        let scale = 2f64.powi(bits as i32);
+        let mut cumulative = 0.0;
+        let mut table: Vec<u64> = Vec::new();
+        for p in folded_gaussian(sigma) {
+            cumulative += p;
+            let entry = ((cumulative * scale) as u64).saturating_sub(1);
+            // Stop once the remaining tail no longer fits in the precision
+            if entry >= (1 << bits) - 1 || table.last() == Some(&entry) {
+                break;
+            }
+            table.push(entry);
+        }
+
+        CdtSampler { table, bits }
+    }
+
+    // Precomputed table, e.g. the CDF tables published with a scheme
+    pub fn from_table(table: &[u64], bits: u32) -> CdtSampler {
+        CdtSampler {
+            table: table.to_vec(),
+            bits,
+        }
+    }
+}
+
+impl Sampler for CdtSampler {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> i64 {
+        let rnd = rng.next_u64() >> (64 - self.bits);
+        let sign = (rng.next_u32() & 1) as i64;
+
+        // |x| = number of entries below the random value, always scanning the full table
+        let mut magnitude: u64 = 0;
+        for &entry in self.table.iter() {
+            magnitude += ct_lt(entry, rnd);
+        }
+        ct_sign(magnitude as i64, sign)
+    }
+
+    fn probability(&self, x: i64) -> f64 {
+        let k = x.unsigned_abs() as usize;
+        if k > self.table.len() {
+            return 0.0;
+        }
+        let upper = if k < self.table.len() {
+            self.table[k] + 1
+        } else {
+            1 << self.bits
+        };
+        let lower = if k == 0 { 0 } else { self.table[k - 1] + 1 };
+        let p = (upper - lower) as f64 / 2f64.powi(self.bits as i32);
+        if k == 0 {
+            p
+        } else {
+            p / 2.0
+        }
+    }
+}
+
+// Knuth-Yao sampler walking the discrete distribution generating tree column by column
+pub struct KnuthYaoSampler {
+    // probabilities[k] = P(|x| = k) as a 64-bit binary fraction
+    pub probabilities: Vec<u64>,
+}
+
+impl KnuthYaoSampler {
+    pub fn new(sigma: f64) -> KnuthYaoSampler {
+        let scale = 2f64.powi(64);
+        KnuthYaoSampler {
+            probabilities: folded_gaussian(sigma)
+                .iter()
+                .map(|p| (p * scale) as u64)
+                .take_while(|&p| p != 0)
+                .collect(),
+        }
+    }
+}
+
+impl Sampler for KnuthYaoSampler {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> i64 {
+        let random = rng.next_u64();
+        let sign = (rng.next_u32() & 1) as i64;
+
+        // The walk never stops early: once a leaf is hit the remaining steps are masked out
+        let mut distance: i64 = 0;
+        let mut hit: i64 = 0;
+        let mut magnitude: i64 = 0;
+        for column in 0..64 {
+            distance = distance
+                .wrapping_mul(2)
+                .wrapping_add(((random >> column) & 1) as i64);
+            for row in (0..self.probabilities.len()).rev() {
+                let bit = ((self.probabilities[row] >> (63 - column)) & 1) as i64;
+                distance = distance.wrapping_sub(bit);
+
+                let found = ct_eq(distance, -1) & (1 ^ hit);
+                magnitude |= -found & row as i64;
+                hit |= found;
+            }
+        }
+        ct_sign(magnitude, sign)
+    }
+
+    fn probability(&self, x: i64) -> f64 {
+        let k = x.unsigned_abs() as usize;
+        if k >= self.probabilities.len() {
+            return 0.0;
+        }
+        let p = self.probabilities[k] as f64 / 2f64.powi(64);
+        // A walk that never hits a leaf returns 0
+        let missing = 1.0
+            - self
+                .probabilities
+                .iter()
+                .map(|&p| p as f64 / 2f64.powi(64))
+                .sum::<f64>();
+        if k == 0 {
+            p + missing
+        } else {
+            p / 2.0
+        }
+    }
+}
+
+// Centered binomial distribution: sum of eta bits minus sum of eta bits, support [-eta, eta]
+pub struct BinomialSampler {
+    pub eta: u32,
+}
+
+impl BinomialSampler {
+    pub fn new(eta: u32) -> BinomialSampler {
+        if eta == 0 || eta > 32 {
+            panic!("Binomial parameter eta must be between 1 and 32!");
+        }
+        BinomialSampler { eta }
+    }
+}
+
+impl Sampler for BinomialSampler {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> i64 {
+        let mask = if self.eta == 32 {
+            u32::MAX
+        } else {
+            (1 << self.eta) - 1
+        };
+        let a = rng.next_u32() & mask;
+        let b = rng.next_u32() & mask;
+        a.count_ones() as i64 - b.count_ones() as i64
+    }
+
+    fn probability(&self, x: i64) -> f64 {
+        let eta = self.eta as i64;
+        if x.abs() > eta {
+            return 0.0;
+        }
+        // C(2 eta, eta + x) / 4^eta
+        let mut p = 1.0;
+        for i in 0..(eta + x) {
+            p *= (2 * eta - i) as f64 / (i + 1) as f64;
+        }
+        p / 4f64.powi(eta as i32)
+    }
+}
+
+// Pearson chi-square statistic of the samples against the sampler's distribution.
+// Values with an expected count below 5 are merged into the outermost bins.
+// Returns (statistic, degrees of freedom).
+pub fn chi_square<S: Sampler>(sampler: &S, samples: &[i64]) -> (f64, usize) {
+    let total = samples.len() as f64;
+
+    let mut bound: i64 = 0;
+    while sampler.probability(bound + 1) * total >= 5.0 {
+        bound += 1;
+    }
+
+    let bins = 2 * bound as usize + 1;
+    let mut observed: Vec<f64> = vec![0.0; bins];
+    for &x in samples {
+        observed[(x.clamp(-bound, bound) + bound) as usize] += 1.0;
+    }
+
+    let mut expected: Vec<f64> = (-bound..=bound)
+        .map(|x| sampler.probability(x) * total)
+        .collect();
+    let tail = (total - expected.iter().sum::<f64>()) / 2.0;
+    expected[0] += tail;
+    expected[bins - 1] += tail;
+
+    let statistic = observed
+        .iter()
+        .zip(expected.iter())
+        .map(|(o, e)| (o - e).powi(2) / e)
+        .sum();
+    (statistic, bins - 1)
+}
+
+// Critical value of the chi-square distribution at the 0.1% significance level
+// (Wilson-Hilferty approximation)
+pub fn chi_square_critical(degrees_of_freedom: usize) -> f64 {
+    let k = degrees_of_freedom as f64;
+    let z = 3.090;
+    k * (1.0 - 2.0 / (9.0 * k) + z * (2.0 / (9.0 * k)).sqrt()).powi(3)
+}